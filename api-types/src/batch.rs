@@ -0,0 +1,71 @@
+use crate::resources::{CreateResource, Resource, ResourceId, UpdateResource};
+use crate::responses::ApiErrorResponse;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_BATCH_SIZE: usize = 500;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum BatchMode {
+    #[default]
+    AllOrNothing,
+    BestEffort,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BatchRequest<T> {
+    #[serde(default)]
+    pub mode: BatchMode,
+    pub items: Vec<T>,
+}
+
+pub type BatchCreate = CreateResource;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BatchUpdate {
+    pub id: ResourceId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_version: Option<i64>,
+    #[serde(flatten)]
+    pub changes: UpdateResource,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BatchDelete {
+    pub id: ResourceId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_version: Option<i64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Succeeded,
+    Failed,
+    RolledBack,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub status: BatchItemStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Resource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ApiErrorResponse>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BatchResponse {
+    pub committed: bool,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BatchItemResult>,
+}
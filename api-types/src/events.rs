@@ -0,0 +1,76 @@
+use crate::audit::AuditAction;
+use crate::resources::Resource;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+pub const RESET_EVENT: &str = "reset";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceEventKind {
+    Created,
+    Updated,
+    Deleted,
+    Restored,
+    Purged,
+}
+
+impl ResourceEventKind {
+    pub const ALL: [ResourceEventKind; 5] = [
+        ResourceEventKind::Created,
+        ResourceEventKind::Updated,
+        ResourceEventKind::Deleted,
+        ResourceEventKind::Restored,
+        ResourceEventKind::Purged,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResourceEventKind::Created => "created",
+            ResourceEventKind::Updated => "updated",
+            ResourceEventKind::Deleted => "deleted",
+            ResourceEventKind::Restored => "restored",
+            ResourceEventKind::Purged => "purged",
+        }
+    }
+}
+
+impl FromStr for ResourceEventKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "created" => Ok(ResourceEventKind::Created),
+            "updated" => Ok(ResourceEventKind::Updated),
+            "deleted" => Ok(ResourceEventKind::Deleted),
+            "restored" => Ok(ResourceEventKind::Restored),
+            "purged" => Ok(ResourceEventKind::Purged),
+            other => Err(format!("Unknown event kind: {other}")),
+        }
+    }
+}
+
+impl From<AuditAction> for ResourceEventKind {
+    fn from(action: AuditAction) -> Self {
+        match action {
+            AuditAction::Created => ResourceEventKind::Created,
+            AuditAction::Updated => ResourceEventKind::Updated,
+            AuditAction::Deleted => ResourceEventKind::Deleted,
+            AuditAction::Restored => ResourceEventKind::Restored,
+            AuditAction::Purged => ResourceEventKind::Purged,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ResourceEvent {
+    pub id: i64,
+    pub kind: ResourceEventKind,
+    pub resource_id: String,
+    pub resource: Option<Resource>,
+    pub actor: String,
+    pub occurred_at: DateTime<Utc>,
+}
@@ -1,2 +1,11 @@
+pub mod audit;
+pub mod batch;
+pub mod events;
+pub mod patch;
+pub mod realtime;
 pub mod resources;
 pub mod responses;
+pub mod revisions;
+pub mod transfer;
+pub mod validation;
+pub mod webhooks;
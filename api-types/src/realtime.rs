@@ -0,0 +1,77 @@
+use crate::events::ResourceEvent;
+use crate::resources::{
+    CreateResource, ListResourcesQuery, Resource, ResourceId, ResourceSearchHit,
+    SearchResourcesQuery, UpdateResource,
+};
+use crate::responses::{ApiErrorResponse, ApiListResponse};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ClientMessage {
+    pub request_id: u32,
+    #[serde(flatten)]
+    pub request: ClientRequest,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientRequest {
+    List {
+        #[serde(default)]
+        query: ListResourcesQuery,
+    },
+    Search {
+        query: SearchResourcesQuery,
+    },
+    Get {
+        id: ResourceId,
+    },
+    Create {
+        input: CreateResource,
+    },
+    Update {
+        id: ResourceId,
+        expected_version: Option<i64>,
+        changes: UpdateResource,
+    },
+    Delete {
+        id: ResourceId,
+        expected_version: Option<i64>,
+    },
+    Restore {
+        id: ResourceId,
+    },
+    Subscribe {
+        last_event_id: Option<i64>,
+    },
+    Unsubscribe,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Resource {
+        request_id: u32,
+        data: Resource,
+    },
+    Resources {
+        request_id: u32,
+        data: ApiListResponse<Resource>,
+    },
+    SearchResults {
+        request_id: u32,
+        data: ApiListResponse<ResourceSearchHit>,
+    },
+    Ack {
+        request_id: u32,
+    },
+    Error {
+        request_id: Option<u32>,
+        status: u16,
+        error: ApiErrorResponse,
+    },
+    Event {
+        event: ResourceEvent,
+    },
+    Reset,
+}
@@ -1,24 +1,123 @@
+use crate::patch::Patch;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 pub type ResourceId = String;
 
+pub const DEFAULT_PAGE_LIMIT: u32 = 50;
+pub const MAX_PAGE_LIMIT: u32 = 200;
+
+pub fn page_limit(limit: Option<u32>) -> u32 {
+    limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT)
+}
+
 #[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Resource {
     pub id: ResourceId,
     pub name: String,
     pub description: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub version: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct CreateResource {
+    #[serde(default)]
     pub name: String,
     pub description: Option<String>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+pub type ReplaceResource = CreateResource;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct UpdateResource {
-    pub name: Option<String>,
-    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Patch::is_unchanged")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    pub name: Patch<String>,
+    #[serde(default, skip_serializing_if = "Patch::is_unchanged")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    pub description: Patch<String>,
+}
+
+impl From<ReplaceResource> for UpdateResource {
+    fn from(input: ReplaceResource) -> Self {
+        Self {
+            name: Patch::Set(input.name),
+            description: input.description.into(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceSort {
+    Name,
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+#[serde(default)]
+pub struct ListResourcesQuery {
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    pub cursor: Option<String>,
+    pub sort: ResourceSort,
+    pub order: SortOrder,
+    pub name_prefix: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+impl ListResourcesQuery {
+    pub fn effective_limit(&self) -> u32 {
+        page_limit(self.limit)
+    }
+}
+
+pub const SEARCH_HIGHLIGHT_START: &str = "\u{2}";
+pub const SEARCH_HIGHLIGHT_END: &str = "\u{3}";
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+#[serde(default)]
+pub struct SearchResourcesQuery {
+    pub q: String,
+    pub limit: Option<u32>,
+}
+
+impl SearchResourcesQuery {
+    pub fn effective_limit(&self) -> u32 {
+        page_limit(self.limit)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ResourceSearchHit {
+    pub resource: Resource,
+    pub rank: f64,
+    /// Matched terms are wrapped in `SEARCH_HIGHLIGHT_START` (U+0002) and `SEARCH_HIGHLIGHT_END` (U+0003).
+    pub name_highlight: String,
+    pub description_snippet: Option<String>,
 }
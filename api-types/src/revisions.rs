@@ -0,0 +1,80 @@
+use crate::resources::page_limit;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ResourceRevision {
+    pub resource_id: String,
+    pub revision: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub actor: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+#[serde(default)]
+pub struct RevisionQuery {
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+impl RevisionQuery {
+    pub fn effective_limit(&self) -> u32 {
+        page_limit(self.limit)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct RevisionDiffQuery {
+    pub from: i64,
+    pub to: i64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FieldChange {
+    pub field: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RevisionDiff {
+    pub resource_id: String,
+    pub from: i64,
+    pub to: i64,
+    pub changes: Vec<FieldChange>,
+}
+
+impl RevisionDiff {
+    pub fn between(from: &ResourceRevision, to: &ResourceRevision) -> Self {
+        let mut changes = Vec::new();
+        if from.name != to.name {
+            changes.push(FieldChange {
+                field: "name".to_string(),
+                from: Some(from.name.clone()),
+                to: Some(to.name.clone()),
+            });
+        }
+        if from.description != to.description {
+            changes.push(FieldChange {
+                field: "description".to_string(),
+                from: from.description.clone(),
+                to: to.description.clone(),
+            });
+        }
+        Self {
+            resource_id: to.resource_id.clone(),
+            from: from.revision,
+            to: to.revision,
+            changes,
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use crate::patch::Patch;
+use crate::resources::{CreateResource, UpdateResource};
+use serde::{Deserialize, Serialize};
+
+pub const NAME_MAX_LENGTH: usize = 200;
+pub const DESCRIPTION_MAX_LENGTH: usize = 2000;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+pub type ValidationResult<T> = Result<T, Vec<FieldError>>;
+
+pub fn validate_name(name: &str, errors: &mut Vec<FieldError>) -> String {
+    let name = name.trim();
+    if name.is_empty() {
+        errors.push(FieldError::new("name", "Name is required"));
+    } else if name.chars().any(char::is_control) {
+        errors.push(FieldError::new(
+            "name",
+            "Name must not contain control characters",
+        ));
+    } else if name.chars().count() > NAME_MAX_LENGTH {
+        errors.push(FieldError::new(
+            "name",
+            format!("Name must be at most {NAME_MAX_LENGTH} characters"),
+        ));
+    }
+    name.to_string()
+}
+
+pub fn validate_description(
+    description: Option<&str>,
+    errors: &mut Vec<FieldError>,
+) -> Option<String> {
+    let description = description
+        .map(str::trim)
+        .filter(|value| !value.is_empty())?;
+    if description
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+    {
+        errors.push(FieldError::new(
+            "description",
+            "Description must not contain control characters other than line breaks and tabs",
+        ));
+    } else if description.chars().count() > DESCRIPTION_MAX_LENGTH {
+        errors.push(FieldError::new(
+            "description",
+            format!("Description must be at most {DESCRIPTION_MAX_LENGTH} characters"),
+        ));
+    }
+    Some(description.to_string())
+}
+
+impl CreateResource {
+    pub fn validate(self) -> ValidationResult<Self> {
+        let mut errors = Vec::new();
+        let name = validate_name(&self.name, &mut errors);
+        let description = validate_description(self.description.as_deref(), &mut errors);
+        if errors.is_empty() {
+            Ok(Self { name, description })
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl UpdateResource {
+    pub fn validate(self) -> ValidationResult<Self> {
+        let mut errors = Vec::new();
+        let name = match self.name {
+            Patch::Unchanged => Patch::Unchanged,
+            Patch::Clear => {
+                errors.push(FieldError::new("name", "Name is required"));
+                Patch::Clear
+            }
+            Patch::Set(name) => Patch::Set(validate_name(&name, &mut errors)),
+        };
+        let description = match self.description {
+            Patch::Unchanged => Patch::Unchanged,
+            Patch::Clear => Patch::Clear,
+            Patch::Set(description) => validate_description(Some(&description), &mut errors).into(),
+        };
+        if errors.is_empty() {
+            Ok(Self { name, description })
+        } else {
+            Err(errors)
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use crate::events::{ResourceEvent, ResourceEventKind};
+use crate::resources::page_limit;
+use crate::validation::{FieldError, ValidationResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+pub const EVENT_HEADER: &str = "x-webhook-event";
+pub const DELIVERY_HEADER: &str = "x-webhook-delivery";
+
+pub const URL_MAX_LENGTH: usize = 2000;
+pub const SECRET_MIN_LENGTH: usize = 16;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    pub events: Vec<ResourceEventKind>,
+    pub active: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateWebhook {
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub events: Vec<ResourceEventKind>,
+    pub secret: Option<String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct UpdateWebhook {
+    pub url: Option<String>,
+    pub events: Option<Vec<ResourceEventKind>>,
+    pub active: Option<bool>,
+    pub rotate_secret: bool,
+}
+
+fn validate_url(url: &str, errors: &mut Vec<FieldError>) -> String {
+    let url = url.trim();
+    if url.is_empty() {
+        errors.push(FieldError::new("url", "URL is required"));
+    } else if !(url.starts_with("http://") || url.starts_with("https://")) {
+        errors.push(FieldError::new("url", "URL must use http or https"));
+    } else if url.len() > URL_MAX_LENGTH {
+        errors.push(FieldError::new(
+            "url",
+            format!("URL must be at most {URL_MAX_LENGTH} characters"),
+        ));
+    }
+    url.to_string()
+}
+
+fn normalize_events(events: Vec<ResourceEventKind>) -> Vec<ResourceEventKind> {
+    if events.is_empty() {
+        return ResourceEventKind::ALL.to_vec();
+    }
+    ResourceEventKind::ALL
+        .into_iter()
+        .filter(|kind| events.contains(kind))
+        .collect()
+}
+
+impl CreateWebhook {
+    pub fn validate(self) -> ValidationResult<Self> {
+        let mut errors = Vec::new();
+        let url = validate_url(&self.url, &mut errors);
+        if let Some(secret) = &self.secret
+            && secret.len() < SECRET_MIN_LENGTH
+        {
+            errors.push(FieldError::new(
+                "secret",
+                format!("Secret must be at least {SECRET_MIN_LENGTH} characters"),
+            ));
+        }
+        if errors.is_empty() {
+            Ok(Self {
+                url,
+                events: normalize_events(self.events),
+                secret: self.secret,
+            })
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl UpdateWebhook {
+    pub fn validate(self) -> ValidationResult<Self> {
+        let mut errors = Vec::new();
+        let url = self.url.map(|url| validate_url(&url, &mut errors));
+        if errors.is_empty() {
+            Ok(Self {
+                url,
+                events: self.events.map(normalize_events),
+                ..self
+            })
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Dead,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Dead => "dead",
+        }
+    }
+}
+
+impl FromStr for DeliveryStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "pending" => Ok(DeliveryStatus::Pending),
+            "delivered" => Ok(DeliveryStatus::Delivered),
+            "dead" => Ok(DeliveryStatus::Dead),
+            other => Err(format!("Unknown delivery status: {other}")),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: String,
+    pub event_id: i64,
+    pub event_kind: ResourceEventKind,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub payload: ResourceEvent,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+#[serde(default)]
+pub struct DeliveryQuery {
+    pub status: Option<DeliveryStatus>,
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+impl DeliveryQuery {
+    pub fn effective_limit(&self) -> u32 {
+        page_limit(self.limit)
+    }
+}
@@ -0,0 +1,25 @@
+CREATE VIRTUAL TABLE IF NOT EXISTS resources_fts USING fts5(
+    name,
+    description,
+    content = 'resources',
+    content_rowid = 'rowid'
+);
+
+CREATE TRIGGER IF NOT EXISTS resources_fts_insert AFTER INSERT ON resources BEGIN
+    INSERT INTO resources_fts (rowid, name, description)
+    VALUES (new.rowid, new.name, new.description);
+END;
+
+CREATE TRIGGER IF NOT EXISTS resources_fts_delete AFTER DELETE ON resources BEGIN
+    INSERT INTO resources_fts (resources_fts, rowid, name, description)
+    VALUES ('delete', old.rowid, old.name, old.description);
+END;
+
+CREATE TRIGGER IF NOT EXISTS resources_fts_update AFTER UPDATE ON resources BEGIN
+    INSERT INTO resources_fts (resources_fts, rowid, name, description)
+    VALUES ('delete', old.rowid, old.name, old.description);
+    INSERT INTO resources_fts (rowid, name, description)
+    VALUES (new.rowid, new.name, new.description);
+END;
+
+INSERT INTO resources_fts (resources_fts) VALUES ('rebuild');
@@ -0,0 +1 @@
+ALTER TABLE resources ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
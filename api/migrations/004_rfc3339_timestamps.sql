@@ -0,0 +1,7 @@
+UPDATE resources
+SET created_at = strftime('%Y-%m-%dT%H:%M:%S.000000Z', CAST(created_at AS INTEGER), 'unixepoch')
+WHERE created_at NOT GLOB '*[^0-9]*';
+
+UPDATE resources
+SET updated_at = strftime('%Y-%m-%dT%H:%M:%S.000000Z', CAST(updated_at AS INTEGER), 'unixepoch')
+WHERE updated_at NOT GLOB '*[^0-9]*';
@@ -0,0 +1,3 @@
+ALTER TABLE resources ADD COLUMN deleted_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_resources_deleted_at ON resources (deleted_at);
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS resource_revisions (
+    resource_id TEXT NOT NULL,
+    revision INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT,
+    actor TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (resource_id, revision)
+);
+
+INSERT OR IGNORE INTO resource_revisions (resource_id, revision, name, description, actor, created_at)
+SELECT id, version, name, description, 'system', updated_at FROM resources;
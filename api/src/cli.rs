@@ -0,0 +1,119 @@
+use crate::config::LogFormat;
+use crate::context::RequestContext;
+use crate::db::SqliteDatabase;
+use crate::transfer::{Encoder, parse_import};
+use api_types::transfer::TransferFormat;
+use clap::{Parser, Subcommand};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "api-server", version, about = "API server for native-leptos")]
+pub struct Cli {
+    /// TOML config file, defaults to ./config.toml when present
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    /// Override bind_address
+    #[arg(long, global = true)]
+    pub bind: Option<String>,
+    /// Override database.url
+    #[arg(long, global = true)]
+    pub database_url: Option<String>,
+    /// Override log_format (text or json)
+    #[arg(long, global = true)]
+    pub log_format: Option<LogFormat>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the HTTP server (default)
+    Serve,
+    /// Export all resources
+    Export {
+        #[arg(long, default_value = "json", value_parser = parse_format)]
+        format: TransferFormat,
+        /// Write to this file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Import resources, upserting by id
+    Import {
+        #[arg(long, default_value = "json", value_parser = parse_format)]
+        format: TransferFormat,
+        /// Validate and report without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Read from this file instead of stdin
+        input: Option<PathBuf>,
+    },
+    #[cfg(feature = "dev-tools")]
+    /// Run a local webhook receiver that verifies and prints deliveries
+    ReceiveWebhooks {
+        #[arg(long, default_value = "127.0.0.1:4000")]
+        listen: std::net::SocketAddr,
+        /// Signing secret returned when the webhook was created
+        #[arg(long)]
+        secret: String,
+        /// Status code to answer with, e.g. 500 to exercise retries
+        #[arg(long, default_value_t = 200)]
+        status: u16,
+    },
+    #[cfg(feature = "dev-tools")]
+    /// Run a local OTLP/HTTP collector stand-in that prints received spans
+    ReceiveTraces {
+        #[arg(long, default_value = "127.0.0.1:4318")]
+        listen: std::net::SocketAddr,
+    },
+}
+
+fn parse_format(value: &str) -> Result<TransferFormat, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| format!("unknown format `{value}`, expected csv, json or ndjson"))
+}
+
+pub async fn export(
+    database: &SqliteDatabase,
+    format: TransferFormat,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer: Box<dyn Write> = match output {
+        Some(path) => Box::new(std::fs::File::create(path)?),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let mut writer = std::io::BufWriter::new(&mut writer);
+    let mut encoder = Encoder::new(format);
+    let mut resources = database.stream_resources();
+    writer.write_all(encoder.header()?.as_bytes())?;
+    while let Some(resource) = resources.recv().await {
+        writer.write_all(encoder.encode(&resource?)?.as_bytes())?;
+    }
+    writer.write_all(encoder.footer().as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub async fn import(
+    database: &SqliteDatabase,
+    format: TransferFormat,
+    dry_run: bool,
+    input: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let body = match input {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => {
+            let mut body = String::new();
+            std::io::stdin().read_to_string(&mut body)?;
+            body
+        }
+    };
+    let rows = parse_import(format, &body)?;
+    let context = RequestContext {
+        actor: "cli".to_string(),
+        request_id: None,
+    };
+    let report = database.import_resources(rows, dry_run, &context).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
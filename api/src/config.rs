@@ -1,25 +1,802 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::env;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-#[derive(Clone)]
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+const MIN_API_KEY_LENGTH: usize = 16;
+const MAX_TRASH_RETENTION_DAYS: i64 = 36_500;
+const MAX_IDEMPOTENCY_TTL_SECS: i64 = 30 * 86_400;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file {}: {source}", .path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to parse config file {}: {source}", .path.display())]
+    Parse {
+        path: PathBuf,
+        source: Box<toml::de::Error>,
+    },
+    #[error("Invalid configuration:\n  - {}", .0.join("\n  - "))]
+    Invalid(Vec<String>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "unknown log format `{value}`, expected text or json"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
     pub bind_address: String,
-    pub database_url: String,
+    pub tls: TlsConfig,
+    pub drain_timeout_secs: u64,
+    pub log_format: LogFormat,
+    pub telemetry: TelemetryConfig,
+    pub database: DatabaseConfig,
     pub auth_enabled: bool,
+    pub api_keys: BTreeMap<String, String>,
+    pub admin_actors: Vec<String>,
+    pub require_if_match: bool,
+    pub trash_retention_days: i64,
+    pub trash_purge_interval_secs: u64,
+    pub webhook_max_attempts: u32,
+    pub webhook_retry_base_secs: u64,
+    pub webhook_timeout_secs: u64,
+    pub webhook_allow_loopback: bool,
+    pub idempotency_ttl_secs: i64,
+    pub rate_limit_enabled: bool,
+    pub rate_limit_per_minute: u32,
+    pub rate_limit_burst: u32,
+    pub search_rate_limit_per_minute: u32,
+    pub import_rate_limit_per_minute: u32,
+    pub health_check_timeout_ms: u64,
+    pub cors: CorsConfig,
+    pub metrics_token: Option<String>,
+    pub docs_enabled: bool,
 }
 
-impl Config {
-    pub fn from_env() -> Self {
-        let database_url = env::var("DATABASE_URL")
-            .unwrap_or_else(|_| "sqlite:./data/app.db?mode=rwc".to_string());
-        let bind_address =
-            env::var("BIND_ADDRESS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
-        let auth_enabled = env::var("AUTH_ENABLED")
-            .map(|value| value == "true" || value == "1")
-            .unwrap_or(false);
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    pub cert_path: Option<String>,
+    pub key_path: Option<String>,
+    pub reload_interval_secs: u64,
+    pub redirect_http_bind: Option<String>,
+    pub public_https_port: Option<u16>,
+}
+
+impl TlsConfig {
+    pub fn enabled(&self) -> bool {
+        self.cert_path.is_some() && self.key_path.is_some()
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub acquire_timeout_secs: u64,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    pub allow_credentials: bool,
+    pub max_age_secs: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum OtlpProtocol {
+    #[serde(rename = "http/protobuf")]
+    Protobuf,
+    #[serde(rename = "http/json")]
+    Json,
+}
+
+impl FromStr for OtlpProtocol {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "http/protobuf" => Ok(Self::Protobuf),
+            "http/json" => Ok(Self::Json),
+            _ => Err(format!(
+                "unknown OTLP protocol `{value}`, expected http/protobuf or http/json"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    pub otlp_endpoint: Option<String>,
+    pub otlp_protocol: OtlpProtocol,
+    pub service_name: String,
+    pub sample_ratio: f64,
+}
+
+#[derive(Default)]
+pub struct Overrides {
+    pub bind_address: Option<String>,
+    pub database_url: Option<String>,
+    pub log_format: Option<LogFormat>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bind_address: "127.0.0.1:3000".to_string(),
+            tls: TlsConfig::default(),
+            drain_timeout_secs: 30,
+            log_format: LogFormat::Text,
+            telemetry: TelemetryConfig::default(),
+            database: DatabaseConfig::default(),
+            auth_enabled: false,
+            api_keys: BTreeMap::new(),
+            admin_actors: Vec::new(),
+            require_if_match: false,
+            trash_retention_days: 30,
+            trash_purge_interval_secs: 3600,
+            webhook_max_attempts: 8,
+            webhook_retry_base_secs: 10,
+            webhook_timeout_secs: 10,
+            webhook_allow_loopback: false,
+            idempotency_ttl_secs: 86400,
+            rate_limit_enabled: true,
+            rate_limit_per_minute: 600,
+            rate_limit_burst: 100,
+            search_rate_limit_per_minute: 120,
+            import_rate_limit_per_minute: 6,
+            health_check_timeout_ms: 2000,
+            cors: CorsConfig::default(),
+            metrics_token: None,
+            docs_enabled: true,
+        }
+    }
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
         Self {
-            bind_address,
-            database_url,
-            auth_enabled,
+            cert_path: None,
+            key_path: None,
+            reload_interval_secs: 30,
+            redirect_http_bind: None,
+            public_https_port: None,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "sqlite:./data/app.db?mode=rwc".to_string(),
+            max_connections: 5,
+            acquire_timeout_secs: 30,
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["http://127.0.0.1:*".to_string()],
+            allowed_methods: ["GET", "POST", "PUT", "PATCH", "DELETE"]
+                .map(String::from)
+                .to_vec(),
+            allowed_headers: [
+                "authorization",
+                "content-type",
+                "if-match",
+                "idempotency-key",
+                "last-event-id",
+                "x-api-key",
+                "x-request-id",
+            ]
+            .map(String::from)
+            .to_vec(),
+            allow_credentials: false,
+            max_age_secs: 600,
+        }
+    }
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            otlp_protocol: OtlpProtocol::Protobuf,
+            service_name: env!("CARGO_BIN_NAME").to_string(),
+            sample_ratio: 1.0,
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: Option<&Path>, overrides: Overrides) -> Result<Self, ConfigError> {
+        Self::load_from(path, overrides, &|name| env::var(name).ok())
+    }
+
+    fn load_from(
+        path: Option<&Path>,
+        overrides: Overrides,
+        lookup: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let path = path.map(Path::to_path_buf).or_else(|| {
+            lookup("API_CONFIG")
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from)
+        });
+        let mut config = match path {
+            Some(path) => Self::from_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?
+            }
+            None => Self::default(),
+        };
+        let mut errors = Vec::new();
+        config.apply_env(&mut EnvOverrides {
+            errors: &mut errors,
+            lookup,
+        });
+        if let Some(bind_address) = overrides.bind_address {
+            config.bind_address = bind_address;
+        }
+        if let Some(database_url) = overrides.database_url {
+            config.database.url = database_url;
+        }
+        if let Some(log_format) = overrides.log_format {
+            config.log_format = log_format;
+        }
+        config.validate(&mut errors);
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+
+    fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&contents).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source: Box::new(source),
+        })
+    }
+
+    fn apply_env(&mut self, env: &mut EnvOverrides) {
+        env.set("BIND_ADDRESS", &mut self.bind_address);
+        env.optional("TLS_CERT_PATH", &mut self.tls.cert_path);
+        env.optional("TLS_KEY_PATH", &mut self.tls.key_path);
+        env.set(
+            "TLS_RELOAD_INTERVAL_SECS",
+            &mut self.tls.reload_interval_secs,
+        );
+        env.optional("TLS_REDIRECT_HTTP_BIND", &mut self.tls.redirect_http_bind);
+        env.optional("TLS_PUBLIC_HTTPS_PORT", &mut self.tls.public_https_port);
+        env.set("DRAIN_TIMEOUT_SECS", &mut self.drain_timeout_secs);
+        env.set("LOG_FORMAT", &mut self.log_format);
+        env.set("DATABASE_URL", &mut self.database.url);
+        env.set(
+            "DATABASE_MAX_CONNECTIONS",
+            &mut self.database.max_connections,
+        );
+        env.set(
+            "DATABASE_ACQUIRE_TIMEOUT_SECS",
+            &mut self.database.acquire_timeout_secs,
+        );
+        env.flag("AUTH_ENABLED", &mut self.auth_enabled);
+        env.api_keys("API_KEYS", &mut self.api_keys);
+        env.list("ADMIN_ACTORS", &mut self.admin_actors);
+        env.flag("REQUIRE_IF_MATCH", &mut self.require_if_match);
+        env.set("TRASH_RETENTION_DAYS", &mut self.trash_retention_days);
+        env.set(
+            "TRASH_PURGE_INTERVAL_SECS",
+            &mut self.trash_purge_interval_secs,
+        );
+        env.set("WEBHOOK_MAX_ATTEMPTS", &mut self.webhook_max_attempts);
+        env.set("WEBHOOK_RETRY_BASE_SECS", &mut self.webhook_retry_base_secs);
+        env.set("WEBHOOK_TIMEOUT_SECS", &mut self.webhook_timeout_secs);
+        env.flag("WEBHOOK_ALLOW_LOOPBACK", &mut self.webhook_allow_loopback);
+        env.set("IDEMPOTENCY_TTL_SECS", &mut self.idempotency_ttl_secs);
+        env.flag("RATE_LIMIT_ENABLED", &mut self.rate_limit_enabled);
+        env.set("RATE_LIMIT_PER_MINUTE", &mut self.rate_limit_per_minute);
+        env.set("RATE_LIMIT_BURST", &mut self.rate_limit_burst);
+        env.set(
+            "SEARCH_RATE_LIMIT_PER_MINUTE",
+            &mut self.search_rate_limit_per_minute,
+        );
+        env.set(
+            "IMPORT_RATE_LIMIT_PER_MINUTE",
+            &mut self.import_rate_limit_per_minute,
+        );
+        env.set("HEALTH_CHECK_TIMEOUT_MS", &mut self.health_check_timeout_ms);
+        env.list("CORS_ALLOWED_ORIGINS", &mut self.cors.allowed_origins);
+        env.list("CORS_ALLOWED_METHODS", &mut self.cors.allowed_methods);
+        env.list("CORS_ALLOWED_HEADERS", &mut self.cors.allowed_headers);
+        env.flag("CORS_ALLOW_CREDENTIALS", &mut self.cors.allow_credentials);
+        env.set("CORS_MAX_AGE_SECS", &mut self.cors.max_age_secs);
+        env.optional("METRICS_TOKEN", &mut self.metrics_token);
+        env.flag("API_DOCS_ENABLED", &mut self.docs_enabled);
+        env.optional(
+            "OTEL_EXPORTER_OTLP_ENDPOINT",
+            &mut self.telemetry.otlp_endpoint,
+        );
+        env.set(
+            "OTEL_EXPORTER_OTLP_PROTOCOL",
+            &mut self.telemetry.otlp_protocol,
+        );
+        env.set("OTEL_SERVICE_NAME", &mut self.telemetry.service_name);
+        env.set("OTEL_TRACES_SAMPLER_ARG", &mut self.telemetry.sample_ratio);
+    }
+
+    fn validate(&self, errors: &mut Vec<String>) {
+        let mut check = |valid: bool, message: &str| {
+            if !valid {
+                errors.push(message.to_string());
+            }
+        };
+        check(
+            self.bind_address.parse::<SocketAddr>().is_ok(),
+            "bind_address must be an ip:port socket address",
+        );
+        check(
+            self.tls.cert_path.is_some() == self.tls.key_path.is_some(),
+            "tls.cert_path and tls.key_path must be set together",
+        );
+        for (name, path) in [
+            ("tls.cert_path", &self.tls.cert_path),
+            ("tls.key_path", &self.tls.key_path),
+        ] {
+            check(
+                path.as_deref().is_none_or(|path| Path::new(path).is_file()),
+                &format!("{name} does not point to a readable file"),
+            );
+        }
+        check(
+            self.tls.reload_interval_secs >= 1,
+            "tls.reload_interval_secs must be at least 1",
+        );
+        if let Some(redirect) = &self.tls.redirect_http_bind {
+            check(
+                self.tls.enabled(),
+                "tls.redirect_http_bind requires tls.cert_path and tls.key_path",
+            );
+            check(
+                redirect.parse::<SocketAddr>().is_ok() && *redirect != self.bind_address,
+                "tls.redirect_http_bind must be an ip:port socket address distinct from bind_address",
+            );
+        }
+        check(
+            !self.database.url.trim().is_empty(),
+            "database.url must not be empty",
+        );
+        check(
+            self.database.max_connections >= 1,
+            "database.max_connections must be at least 1",
+        );
+        check(
+            self.database.acquire_timeout_secs >= 1,
+            "database.acquire_timeout_secs must be at least 1",
+        );
+        check(
+            !self.auth_enabled || !self.api_keys.is_empty(),
+            "auth_enabled requires at least one entry in api_keys",
+        );
+        check(
+            self.api_keys
+                .values()
+                .all(|key| key.len() >= MIN_API_KEY_LENGTH),
+            &format!("api_keys values must be at least {MIN_API_KEY_LENGTH} characters"),
+        );
+        check(
+            !self.auth_enabled
+                || self
+                    .admin_actors
+                    .iter()
+                    .all(|actor| self.api_keys.contains_key(actor)),
+            "admin_actors must name actors from api_keys when auth_enabled is set",
+        );
+        check(
+            (0..=MAX_TRASH_RETENTION_DAYS).contains(&self.trash_retention_days),
+            &format!("trash_retention_days must be between 0 and {MAX_TRASH_RETENTION_DAYS}"),
+        );
+        check(
+            self.trash_purge_interval_secs >= 1,
+            "trash_purge_interval_secs must be at least 1",
+        );
+        check(
+            self.webhook_max_attempts >= 1,
+            "webhook_max_attempts must be at least 1",
+        );
+        check(
+            self.webhook_timeout_secs >= 1,
+            "webhook_timeout_secs must be at least 1",
+        );
+        check(
+            (1..=MAX_IDEMPOTENCY_TTL_SECS).contains(&self.idempotency_ttl_secs),
+            &format!("idempotency_ttl_secs must be between 1 and {MAX_IDEMPOTENCY_TTL_SECS}"),
+        );
+        check(
+            !self.rate_limit_enabled
+                || [
+                    self.rate_limit_per_minute,
+                    self.rate_limit_burst,
+                    self.search_rate_limit_per_minute,
+                    self.import_rate_limit_per_minute,
+                ]
+                .iter()
+                .all(|limit| *limit >= 1),
+            "rate limits must be at least 1 when rate_limit_enabled is set",
+        );
+        check(
+            self.health_check_timeout_ms >= 1,
+            "health_check_timeout_ms must be at least 1",
+        );
+        check(
+            !self.cors.allowed_origins.is_empty(),
+            "cors.allowed_origins must not be empty",
+        );
+        check(
+            !self.cors.allow_credentials || !self.cors.allowed_origins.iter().any(|o| o == "*"),
+            "cors.allow_credentials cannot be combined with a `*` origin",
+        );
+        check(
+            self.telemetry
+                .otlp_endpoint
+                .as_deref()
+                .is_none_or(|endpoint| {
+                    endpoint.starts_with("http://") || endpoint.starts_with("https://")
+                }),
+            "telemetry.otlp_endpoint must be an http:// or https:// URL",
+        );
+        check(
+            (0.0..=1.0).contains(&self.telemetry.sample_ratio),
+            "telemetry.sample_ratio must be between 0 and 1",
+        );
+    }
+}
+
+struct EnvOverrides<'a> {
+    errors: &'a mut Vec<String>,
+    lookup: &'a dyn Fn(&str) -> Option<String>,
+}
+
+impl EnvOverrides<'_> {
+    fn read(&self, name: &str) -> Option<String> {
+        (self.lookup)(name).map(|value| value.trim().to_string())
+    }
+
+    fn set<T: FromStr>(&mut self, name: &str, target: &mut T) {
+        let Some(value) = self.read(name) else {
+            return;
+        };
+        match value.parse() {
+            Ok(parsed) => *target = parsed,
+            Err(_) => self.errors.push(format!("{name}: cannot parse `{value}`")),
+        }
+    }
+
+    fn flag(&mut self, name: &str, target: &mut bool) {
+        match self.read(name).as_deref() {
+            None => {}
+            Some("true" | "1") => *target = true,
+            Some("false" | "0") => *target = false,
+            Some(value) => self.errors.push(format!(
+                "{name}: expected true, false, 1 or 0, got `{value}`"
+            )),
+        }
+    }
+
+    fn optional<T: FromStr>(&mut self, name: &str, target: &mut Option<T>) {
+        match self.read(name) {
+            None => {}
+            Some(value) if value.is_empty() => *target = None,
+            Some(value) => match value.parse() {
+                Ok(parsed) => *target = Some(parsed),
+                Err(_) => self.errors.push(format!("{name}: cannot parse `{value}`")),
+            },
+        }
+    }
+
+    fn list(&mut self, name: &str, target: &mut Vec<String>) {
+        if let Some(value) = self.read(name) {
+            *target = split_list(&value).map(String::from).collect();
+        }
+    }
+
+    fn api_keys(&mut self, name: &str, target: &mut BTreeMap<String, String>) {
+        let Some(value) = self.read(name) else {
+            return;
+        };
+        target.clear();
+        for entry in split_list(&value) {
+            match entry.split_once(':') {
+                Some((actor, key)) if !actor.trim().is_empty() => {
+                    target.insert(actor.trim().to_string(), key.trim().to_string());
+                }
+                _ => self.errors.push(format!(
+                    "{name}: expected comma-separated actor:key entries"
+                )),
+            }
+        }
+    }
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn load(
+        file: Option<&str>,
+        vars: &[(&str, &str)],
+        overrides: Overrides,
+    ) -> Result<Config, ConfigError> {
+        static NEXT_FILE: AtomicUsize = AtomicUsize::new(0);
+        let path = file.map(|contents| {
+            let path = env::temp_dir().join(format!(
+                "api-config-test-{}-{}.toml",
+                std::process::id(),
+                NEXT_FILE.fetch_add(1, Ordering::Relaxed)
+            ));
+            std::fs::write(&path, contents).unwrap();
+            path
+        });
+        let vars: BTreeMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let result = Config::load_from(path.as_deref(), overrides, &|name| vars.get(name).cloned());
+        if let Some(path) = path {
+            std::fs::remove_file(path).unwrap();
+        }
+        result
+    }
+
+    fn errors(result: Result<Config, ConfigError>) -> Vec<String> {
+        match result {
+            Err(ConfigError::Invalid(errors)) => errors,
+            Err(error) => panic!("unexpected error: {error}"),
+            Ok(_) => Vec::new(),
+        }
+    }
+
+    type Change = Box<dyn FnOnce(&mut Config)>;
+
+    fn invalid(change: impl FnOnce(&mut Config)) -> Vec<String> {
+        let mut config = Config::default();
+        change(&mut config);
+        let mut errors = Vec::new();
+        config.validate(&mut errors);
+        errors
+    }
+
+    #[test]
+    fn cli_overrides_env_which_overrides_the_file_which_overrides_defaults() {
+        let file = r#"
+            bind_address = "127.0.0.1:4001"
+            rate_limit_per_minute = 100
+            docs_enabled = false
+            [database]
+            url = "sqlite:file.db"
+        "#;
+        let vars = [
+            ("BIND_ADDRESS", "127.0.0.1:4002"),
+            ("RATE_LIMIT_PER_MINUTE", "50"),
+            ("DATABASE_URL", "sqlite:env.db"),
+        ];
+        let config = load(
+            Some(file),
+            &vars,
+            Overrides {
+                bind_address: Some("127.0.0.1:4003".to_string()),
+                ..Overrides::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(config.bind_address, "127.0.0.1:4003");
+        assert_eq!(config.database.url, "sqlite:env.db");
+        assert_eq!(config.rate_limit_per_minute, 50);
+        assert!(!config.docs_enabled);
+        assert_eq!(config.trash_retention_days, 30);
+
+        let config = load(Some(file), &[], Overrides::default()).unwrap();
+        assert_eq!(config.bind_address, "127.0.0.1:4001");
+        assert_eq!(config.database.url, "sqlite:file.db");
+    }
+
+    #[test]
+    fn unparseable_env_values_are_reported_together() {
+        let vars = [
+            ("RATE_LIMIT_BURST", "lots"),
+            ("AUTH_ENABLED", "yes"),
+            ("API_KEYS", "missing-separator"),
+        ];
+        assert_eq!(
+            errors(load(None, &vars, Overrides::default())),
+            [
+                "AUTH_ENABLED: expected true, false, 1 or 0, got `yes`",
+                "API_KEYS: expected comma-separated actor:key entries",
+                "RATE_LIMIT_BURST: cannot parse `lots`",
+            ]
+        );
+        assert!(matches!(
+            load(Some("unknown_key = 1"), &[], Overrides::default()),
+            Err(ConfigError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn each_invalid_setting_is_reported() {
+        const READABLE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+        let cases: Vec<(Change, &str)> = vec![
+            (
+                Box::new(|c| c.bind_address = "localhost".into()),
+                "bind_address must be an ip:port socket address",
+            ),
+            (
+                Box::new(|c| c.tls.key_path = Some(READABLE.into())),
+                "tls.cert_path and tls.key_path must be set together",
+            ),
+            (
+                Box::new(|c| {
+                    c.tls.cert_path = Some("/missing/cert.pem".into());
+                    c.tls.key_path = Some(READABLE.into());
+                }),
+                "tls.cert_path does not point to a readable file",
+            ),
+            (
+                Box::new(|c| c.tls.reload_interval_secs = 0),
+                "tls.reload_interval_secs must be at least 1",
+            ),
+            (
+                Box::new(|c| c.tls.redirect_http_bind = Some("127.0.0.1:8080".into())),
+                "tls.redirect_http_bind requires tls.cert_path and tls.key_path",
+            ),
+            (
+                Box::new(|c| {
+                    c.tls.cert_path = Some(READABLE.into());
+                    c.tls.key_path = Some(READABLE.into());
+                    c.tls.redirect_http_bind = Some(c.bind_address.clone());
+                }),
+                "tls.redirect_http_bind must be an ip:port socket address distinct from bind_address",
+            ),
+            (
+                Box::new(|c| c.database.url = " ".into()),
+                "database.url must not be empty",
+            ),
+            (
+                Box::new(|c| c.database.max_connections = 0),
+                "database.max_connections must be at least 1",
+            ),
+            (
+                Box::new(|c| c.database.acquire_timeout_secs = 0),
+                "database.acquire_timeout_secs must be at least 1",
+            ),
+            (
+                Box::new(|c| c.auth_enabled = true),
+                "auth_enabled requires at least one entry in api_keys",
+            ),
+            (
+                Box::new(|c| {
+                    c.api_keys.insert("desktop".into(), "short".into());
+                }),
+                "api_keys values must be at least 16 characters",
+            ),
+            (
+                Box::new(|c| {
+                    c.auth_enabled = true;
+                    c.api_keys
+                        .insert("desktop".into(), "a-sufficiently-long-key".into());
+                    c.admin_actors = vec!["anonymous".into()];
+                }),
+                "admin_actors must name actors from api_keys when auth_enabled is set",
+            ),
+            (
+                Box::new(|c| c.trash_retention_days = -1),
+                "trash_retention_days must be between 0 and 36500",
+            ),
+            (
+                Box::new(|c| c.trash_retention_days = i64::MAX),
+                "trash_retention_days must be between 0 and 36500",
+            ),
+            (
+                Box::new(|c| c.trash_purge_interval_secs = 0),
+                "trash_purge_interval_secs must be at least 1",
+            ),
+            (
+                Box::new(|c| c.webhook_max_attempts = 0),
+                "webhook_max_attempts must be at least 1",
+            ),
+            (
+                Box::new(|c| c.webhook_timeout_secs = 0),
+                "webhook_timeout_secs must be at least 1",
+            ),
+            (
+                Box::new(|c| c.idempotency_ttl_secs = 0),
+                "idempotency_ttl_secs must be between 1 and 2592000",
+            ),
+            (
+                Box::new(|c| c.idempotency_ttl_secs = i64::MAX),
+                "idempotency_ttl_secs must be between 1 and 2592000",
+            ),
+            (
+                Box::new(|c| c.import_rate_limit_per_minute = 0),
+                "rate limits must be at least 1 when rate_limit_enabled is set",
+            ),
+            (
+                Box::new(|c| c.health_check_timeout_ms = 0),
+                "health_check_timeout_ms must be at least 1",
+            ),
+            (
+                Box::new(|c| c.cors.allowed_origins.clear()),
+                "cors.allowed_origins must not be empty",
+            ),
+            (
+                Box::new(|c| {
+                    c.cors.allowed_origins = vec!["*".into()];
+                    c.cors.allow_credentials = true;
+                }),
+                "cors.allow_credentials cannot be combined with a `*` origin",
+            ),
+            (
+                Box::new(|c| c.telemetry.otlp_endpoint = Some("127.0.0.1:4318".into())),
+                "telemetry.otlp_endpoint must be an http:// or https:// URL",
+            ),
+            (
+                Box::new(|c| c.telemetry.sample_ratio = 1.5),
+                "telemetry.sample_ratio must be between 0 and 1",
+            ),
+        ];
+        assert!(invalid(|_| {}).is_empty());
+        for (change, expected) in cases {
+            assert_eq!(invalid(change), [expected]);
         }
+        assert!(
+            invalid(|c| {
+                c.rate_limit_enabled = false;
+                c.import_rate_limit_per_minute = 0;
+            })
+            .is_empty()
+        );
     }
 }
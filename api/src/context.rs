@@ -0,0 +1,39 @@
+use crate::middleware::Actor;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use std::convert::Infallible;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+#[derive(Clone, Debug)]
+pub struct RequestContext {
+    pub actor: String,
+    pub request_id: Option<String>,
+}
+
+impl RequestContext {
+    pub fn system() -> Self {
+        Self {
+            actor: "system".to_string(),
+            request_id: None,
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for RequestContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let actor = parts
+            .extensions
+            .get::<Actor>()
+            .map(|actor| actor.0.clone())
+            .unwrap_or_else(|| Actor::anonymous().0);
+        let request_id = parts
+            .headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        Ok(Self { actor, request_id })
+    }
+}
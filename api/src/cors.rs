@@ -0,0 +1,220 @@
+use crate::config::CorsConfig;
+use axum::http::{HeaderName, Method, header};
+use std::time::Duration;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+const EXPOSED_HEADERS: [&str; 7] = [
+    "etag",
+    "retry-after",
+    "ratelimit-limit",
+    "ratelimit-remaining",
+    "ratelimit-reset",
+    "idempotent-replayed",
+    "x-request-id",
+];
+
+pub fn cors_layer(config: &CorsConfig) -> CorsLayer {
+    let origins = config.allowed_origins.clone();
+    let methods = if config.allowed_methods.iter().any(|method| method == "*") {
+        AllowMethods::mirror_request()
+    } else {
+        AllowMethods::list(parse_all(&config.allowed_methods, |method| {
+            Method::from_bytes(method.to_ascii_uppercase().as_bytes()).ok()
+        }))
+    };
+    let headers = if config.allowed_headers.iter().any(|name| name == "*") {
+        AllowHeaders::mirror_request()
+    } else {
+        AllowHeaders::list(parse_all(&config.allowed_headers, |name| {
+            HeaderName::from_bytes(name.to_ascii_lowercase().as_bytes()).ok()
+        }))
+    };
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(move |origin, _| {
+            origin.to_str().is_ok_and(|origin| {
+                origins
+                    .iter()
+                    .any(|allowed| origin_matches(allowed, origin))
+            })
+        }))
+        .allow_methods(methods)
+        .allow_headers(headers)
+        .allow_credentials(config.allow_credentials)
+        .max_age(Duration::from_secs(config.max_age_secs))
+        .expose_headers(EXPOSED_HEADERS.map(HeaderName::from_static))
+        .vary([header::ORIGIN])
+}
+
+fn parse_all<T>(values: &[String], parse: impl Fn(&str) -> Option<T>) -> Vec<T> {
+    values
+        .iter()
+        .filter_map(|value| {
+            let parsed = parse(value);
+            if parsed.is_none() {
+                tracing::warn!("Ignoring invalid CORS entry `{value}`");
+            }
+            parsed
+        })
+        .collect()
+}
+
+fn origin_matches(allowed: &str, origin: &str) -> bool {
+    if allowed == "*" {
+        return true;
+    }
+    match allowed.strip_suffix(":*") {
+        Some(prefix) => origin
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_prefix(':'))
+            .is_some_and(|port| !port.is_empty() && port.bytes().all(|byte| byte.is_ascii_digit())),
+        None => allowed.eq_ignore_ascii_case(origin),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::{Request, Response, StatusCode};
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    fn app(config: &CorsConfig) -> Router {
+        Router::new()
+            .route("/resources", get(|| async { "ok" }).post(|| async { "ok" }))
+            .layer(cors_layer(config))
+    }
+
+    async fn preflight(config: &CorsConfig, origin: &str, method: &str) -> Response<Body> {
+        app(config)
+            .oneshot(
+                Request::builder()
+                    .method(Method::OPTIONS)
+                    .uri("/resources")
+                    .header(header::ORIGIN, origin)
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, method)
+                    .header(
+                        header::ACCESS_CONTROL_REQUEST_HEADERS,
+                        "content-type,if-match",
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    fn header_value(response: &Response<Body>, name: HeaderName) -> Option<&str> {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    }
+
+    #[tokio::test]
+    async fn preflight_allows_desktop_origin_by_default() {
+        let response = preflight(&CorsConfig::default(), "http://127.0.0.1:51234", "PATCH").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            header_value(&response, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some("http://127.0.0.1:51234")
+        );
+        let methods = header_value(&response, header::ACCESS_CONTROL_ALLOW_METHODS).unwrap();
+        assert!(methods.contains("PATCH"));
+        let headers = header_value(&response, header::ACCESS_CONTROL_ALLOW_HEADERS).unwrap();
+        assert!(headers.contains("if-match"));
+        assert_eq!(
+            header_value(&response, header::ACCESS_CONTROL_MAX_AGE),
+            Some("600")
+        );
+        assert_eq!(
+            header_value(&response, header::ACCESS_CONTROL_ALLOW_CREDENTIALS),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn preflight_rejects_unlisted_origins() {
+        for origin in [
+            "https://evil.example",
+            "http://127.0.0.1",
+            "http://127.0.0.1:8080.evil.example",
+            "http://localhost:3000",
+        ] {
+            let response = preflight(&CorsConfig::default(), origin, "GET").await;
+            assert_eq!(
+                header_value(&response, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+                None,
+                "{origin} should not be allowed"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn preflight_honours_configured_policy() {
+        let config = CorsConfig {
+            allowed_origins: vec!["https://app.example".to_string()],
+            allowed_methods: vec!["GET".to_string()],
+            allow_credentials: true,
+            max_age_secs: 60,
+            ..CorsConfig::default()
+        };
+        let response = preflight(&config, "https://app.example", "GET").await;
+        assert_eq!(
+            header_value(&response, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some("https://app.example")
+        );
+        assert_eq!(
+            header_value(&response, header::ACCESS_CONTROL_ALLOW_METHODS),
+            Some("GET")
+        );
+        assert_eq!(
+            header_value(&response, header::ACCESS_CONTROL_ALLOW_CREDENTIALS),
+            Some("true")
+        );
+        assert_eq!(
+            header_value(&response, header::ACCESS_CONTROL_MAX_AGE),
+            Some("60")
+        );
+
+        let response = preflight(&config, "http://127.0.0.1:8080", "GET").await;
+        assert_eq!(
+            header_value(&response, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn simple_requests_expose_response_headers() {
+        let response = app(&CorsConfig::default())
+            .oneshot(
+                Request::builder()
+                    .uri("/resources")
+                    .header(header::ORIGIN, "http://127.0.0.1:8080")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let exposed = header_value(&response, header::ACCESS_CONTROL_EXPOSE_HEADERS).unwrap();
+        assert!(exposed.contains("etag"));
+        assert!(exposed.contains("retry-after"));
+    }
+
+    #[test]
+    fn origin_patterns() {
+        assert!(origin_matches("*", "https://anything.example"));
+        assert!(origin_matches(
+            "http://127.0.0.1:*",
+            "http://127.0.0.1:3000"
+        ));
+        assert!(!origin_matches("http://127.0.0.1:*", "http://127.0.0.1:"));
+        assert!(!origin_matches(
+            "http://127.0.0.1:*",
+            "https://127.0.0.1:3000"
+        ));
+        assert!(origin_matches("https://App.example", "https://app.example"));
+    }
+}
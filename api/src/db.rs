@@ -1,8 +1,38 @@
+mod audit;
+mod batch;
+mod events;
+mod health;
+mod idempotency;
+mod revisions;
+mod stats;
+mod transfer;
+mod webhooks;
+
+pub use batch::BatchOperation;
+pub use idempotency::{IdempotencyState, StoredResponse};
+pub use stats::{PoolStats, ResourceCounts};
+pub use webhooks::DueDelivery;
+
+use events::EventFeed;
+
+use crate::config::DatabaseConfig;
+use crate::context::RequestContext;
 use crate::error::ApiError;
-use api_types::resources::{CreateResource, Resource, UpdateResource};
-use sqlx::Row;
-use sqlx::sqlite::{SqlitePool, SqlitePoolOptions, SqliteRow};
-use std::time::{SystemTime, UNIX_EPOCH};
+use api_types::audit::AuditAction;
+use api_types::resources::{
+    CreateResource, ListResourcesQuery, Resource, ResourceSearchHit, ResourceSort,
+    SEARCH_HIGHLIGHT_END, SEARCH_HIGHLIGHT_START, SearchResourcesQuery, SortOrder, UpdateResource,
+};
+use api_types::responses::ApiListResponse;
+use chrono::{DateTime, SecondsFormat, Utc};
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::{Sqlite, SqliteExecutor, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::{QueryBuilder, Row};
+use sqlx::{SqliteConnection, Transaction};
+use std::time::Duration;
+use uuid::Uuid;
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
 fn row_to_resource(row: SqliteRow) -> Resource {
     Resource {
@@ -11,6 +41,8 @@ fn row_to_resource(row: SqliteRow) -> Resource {
         description: row.get("description"),
         created_at: row.get("created_at"),
         updated_at: row.get("updated_at"),
+        version: row.get("version"),
+        deleted_at: row.get("deleted_at"),
     }
 }
 
@@ -21,108 +53,803 @@ fn db_err(error: sqlx::Error) -> ApiError {
 #[derive(Clone)]
 pub struct SqliteDatabase {
     pool: SqlitePool,
+    events: EventFeed,
 }
 
 impl SqliteDatabase {
-    pub async fn new(database_url: &str) -> Result<Self, ApiError> {
+    #[tracing::instrument(skip_all)]
+    pub async fn new(config: &DatabaseConfig) -> Result<Self, ApiError> {
         let pool = SqlitePoolOptions::new()
-            .max_connections(5)
-            .connect(database_url)
+            .max_connections(config.max_connections)
+            .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+            .connect(&config.url)
             .await
             .map_err(db_err)?;
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            events: EventFeed::new(),
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn close(&self) {
+        self.pool.close().await;
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn migrate(&self) -> Result<(), ApiError> {
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS resources (
-                id TEXT PRIMARY KEY NOT NULL,
-                name TEXT NOT NULL,
-                description TEXT,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            )",
-        )
-        .execute(&self.pool)
-        .await
-        .map_err(db_err)?;
-        Ok(())
+        MIGRATOR
+            .run(&self.pool)
+            .await
+            .map_err(|error| ApiError::Database(error.to_string()))?;
+        self.init_events().await
     }
 
-    pub async fn list_resources(&self) -> Result<Vec<Resource>, ApiError> {
-        let rows = sqlx::query("SELECT id, name, description, created_at, updated_at FROM resources ORDER BY created_at DESC")
-            .fetch_all(&self.pool)
+    #[tracing::instrument(skip_all)]
+    pub async fn list_resources(
+        &self,
+        query: &ListResourcesQuery,
+        trashed: bool,
+    ) -> Result<ApiListResponse<Resource>, ApiError> {
+        let column = sort_column(query.sort);
+        let direction = match query.order {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        };
+        let limit = query.effective_limit();
+
+        let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM resources WHERE 1 = 1");
+        push_filters(&mut count, query, trashed);
+        let total: i64 = count
+            .build_query_scalar()
+            .fetch_one(&self.pool)
             .await
             .map_err(db_err)?;
-        Ok(rows.into_iter().map(row_to_resource).collect())
+
+        let mut select = QueryBuilder::<Sqlite>::new(
+            "SELECT id, name, description, created_at, updated_at, version, deleted_at FROM resources WHERE 1 = 1",
+        );
+        push_filters(&mut select, query, trashed);
+        if let Some(cursor) = &query.cursor {
+            let (value, id) = decode_cursor(cursor, query.sort, query.order)?;
+            let comparison = match query.order {
+                SortOrder::Asc => ">",
+                SortOrder::Desc => "<",
+            };
+            select
+                .push(format!(" AND ({column}, id) {comparison} ("))
+                .push_bind(value)
+                .push(", ")
+                .push_bind(id)
+                .push(")");
+        }
+        select.push(format!(
+            " ORDER BY {column} {direction}, id {direction} LIMIT "
+        ));
+        select.push_bind(i64::from(limit) + 1);
+        if query.cursor.is_none() {
+            select
+                .push(" OFFSET ")
+                .push_bind(i64::from(query.offset.unwrap_or(0)));
+        }
+
+        let rows = select.build().fetch_all(&self.pool).await.map_err(db_err)?;
+        let mut data: Vec<Resource> = rows.into_iter().map(row_to_resource).collect();
+        let next_cursor = if data.len() > limit as usize {
+            data.truncate(limit as usize);
+            data.last().map(|resource| {
+                encode_cursor(
+                    query.sort,
+                    query.order,
+                    &sort_value(resource, query.sort),
+                    &resource.id,
+                )
+            })
+        } else {
+            None
+        };
+        Ok(ApiListResponse {
+            data,
+            total: total as usize,
+            next_cursor,
+        })
     }
 
-    pub async fn get_resource(&self, id: &str) -> Result<Option<Resource>, ApiError> {
-        sqlx::query(
-            "SELECT id, name, description, created_at, updated_at FROM resources WHERE id = ?",
+    #[tracing::instrument(skip_all)]
+    pub async fn search_resources(
+        &self,
+        query: &SearchResourcesQuery,
+    ) -> Result<ApiListResponse<ResourceSearchHit>, ApiError> {
+        let expression = fts_expression(&query.q)
+            .ok_or_else(|| ApiError::BadRequest("Search query is empty".to_string()))?;
+        let total: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM resources_fts
+                JOIN resources r ON r.rowid = resources_fts.rowid
+                WHERE resources_fts MATCH ? AND r.deleted_at IS NULL",
         )
-        .bind(id)
-        .fetch_optional(&self.pool)
+        .bind(&expression)
+        .fetch_one(&self.pool)
         .await
-        .map_err(db_err)
-        .map(|row| row.map(row_to_resource))
-    }
-
-    pub async fn create_resource(&self, input: CreateResource) -> Result<Resource, ApiError> {
-        let id = generate_id();
-        let now = now_secs();
-        sqlx::query("INSERT INTO resources (id, name, description, created_at, updated_at) VALUES (?, ?, ?, ?, ?)")
-            .bind(&id)
-            .bind(&input.name)
-            .bind(&input.description)
-            .bind(&now)
-            .bind(&now)
-            .execute(&self.pool)
-            .await
-            .map_err(db_err)?;
-        self.get_resource(&id)
+        .map_err(db_err)?;
+        let rows = sqlx::query(
+            "SELECT r.id, r.name, r.description, r.created_at, r.updated_at, r.version, r.deleted_at,
+                bm25(resources_fts) AS rank,
+                highlight(resources_fts, 0, ?, ?) AS name_highlight,
+                snippet(resources_fts, 1, ?, ?, '…', 16) AS description_snippet
+            FROM resources_fts
+            JOIN resources r ON r.rowid = resources_fts.rowid
+            WHERE resources_fts MATCH ? AND r.deleted_at IS NULL
+            ORDER BY rank
+            LIMIT ?",
+        )
+        .bind(SEARCH_HIGHLIGHT_START)
+        .bind(SEARCH_HIGHLIGHT_END)
+        .bind(SEARCH_HIGHLIGHT_START)
+        .bind(SEARCH_HIGHLIGHT_END)
+        .bind(&expression)
+        .bind(i64::from(query.effective_limit()))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_err)?;
+        let data = rows
+            .into_iter()
+            .map(|row| {
+                let rank: f64 = row.get("rank");
+                let name_highlight: String = row.get("name_highlight");
+                let description_snippet: Option<String> = row.get("description_snippet");
+                ResourceSearchHit {
+                    resource: row_to_resource(row),
+                    rank,
+                    name_highlight,
+                    description_snippet: description_snippet.filter(|snippet| !snippet.is_empty()),
+                }
+            })
+            .collect();
+        Ok(ApiListResponse {
+            data,
+            total: total as usize,
+            next_cursor: None,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn get_resource(&self, id: &str) -> Result<Option<Resource>, ApiError> {
+        Ok(fetch_resource(&self.pool, id)
             .await?
-            .ok_or_else(|| ApiError::Database("Insert failed".to_string()))
+            .filter(|resource| resource.deleted_at.is_none()))
     }
 
+    #[tracing::instrument(skip_all)]
+    pub async fn create_resource(
+        &self,
+        input: CreateResource,
+        context: &RequestContext,
+    ) -> Result<Resource, ApiError> {
+        let mut transaction = self.begin().await?;
+        let resource = insert(&mut transaction, input, context).await?;
+        self.commit(transaction).await?;
+        Ok(resource)
+    }
+
+    #[tracing::instrument(skip_all)]
     pub async fn update_resource(
         &self,
         id: &str,
         input: UpdateResource,
+        expected_version: Option<i64>,
+        context: &RequestContext,
     ) -> Result<Option<Resource>, ApiError> {
-        let result = sqlx::query("UPDATE resources SET name = COALESCE(?, name), description = COALESCE(?, description), updated_at = ? WHERE id = ?")
-            .bind(&input.name)
-            .bind(&input.description)
-            .bind(now_secs())
-            .bind(id)
-            .execute(&self.pool)
-            .await
-            .map_err(db_err)?;
-        if result.rows_affected() == 0 {
+        let mut transaction = self.begin().await?;
+        let resource = update(&mut transaction, id, input, expected_version, context).await?;
+        self.commit(transaction).await?;
+        Ok(resource)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn delete_resource(
+        &self,
+        id: &str,
+        expected_version: Option<i64>,
+        context: &RequestContext,
+    ) -> Result<bool, ApiError> {
+        let mut transaction = self.begin().await?;
+        let deleted = soft_delete(&mut transaction, id, expected_version, context).await?;
+        self.commit(transaction).await?;
+        Ok(deleted)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn restore_resource(
+        &self,
+        id: &str,
+        context: &RequestContext,
+    ) -> Result<Option<Resource>, ApiError> {
+        let mut transaction = self.begin().await?;
+        let Some(before) = fetch_resource(&mut *transaction, id)
+            .await?
+            .filter(|resource| resource.deleted_at.is_some())
+        else {
             return Ok(None);
+        };
+        sqlx::query(
+            "UPDATE resources SET deleted_at = NULL, updated_at = ?, version = version + 1 WHERE id = ?",
+        )
+        .bind(timestamp(Utc::now()))
+        .bind(id)
+        .execute(&mut *transaction)
+        .await
+        .map_err(db_err)?;
+        let after = fetch_resource(&mut *transaction, id).await?;
+        audit::record(
+            &mut transaction,
+            context,
+            id,
+            AuditAction::Restored,
+            Some(&before),
+            after.as_ref(),
+        )
+        .await?;
+        if let Some(after) = &after {
+            revisions::record(&mut transaction, context, after).await?;
         }
-        self.get_resource(id).await
+        self.commit(transaction).await?;
+        Ok(after)
     }
 
-    pub async fn delete_resource(&self, id: &str) -> Result<bool, ApiError> {
-        let result = sqlx::query("DELETE FROM resources WHERE id = ?")
-            .bind(id)
-            .execute(&self.pool)
+    #[tracing::instrument(skip_all)]
+    pub async fn purge_resource(
+        &self,
+        id: &str,
+        context: &RequestContext,
+    ) -> Result<bool, ApiError> {
+        let mut transaction = self.begin().await?;
+        let Some(before) = fetch_resource(&mut *transaction, id)
+            .await?
+            .filter(|resource| resource.deleted_at.is_some())
+        else {
+            return Ok(false);
+        };
+        purge(&mut transaction, &before, context).await?;
+        self.commit(transaction).await?;
+        Ok(true)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn purge_deleted_before(&self, cutoff: DateTime<Utc>) -> Result<u64, ApiError> {
+        let mut transaction = self.begin().await?;
+        let rows = sqlx::query(
+            "SELECT id, name, description, created_at, updated_at, version, deleted_at
+            FROM resources WHERE deleted_at IS NOT NULL AND deleted_at < ?",
+        )
+        .bind(timestamp(cutoff))
+        .fetch_all(&mut *transaction)
+        .await
+        .map_err(db_err)?;
+        let context = RequestContext::system();
+        let count = rows.len() as u64;
+        for resource in rows.into_iter().map(row_to_resource) {
+            purge(&mut transaction, &resource, &context).await?;
+        }
+        self.commit(transaction).await?;
+        Ok(count)
+    }
+
+    async fn begin(&self) -> Result<Transaction<'static, Sqlite>, ApiError> {
+        self.pool
+            .begin_with("BEGIN IMMEDIATE")
             .await
-            .map_err(db_err)?;
-        Ok(result.rows_affected() > 0)
+            .map_err(db_err)
+    }
+
+    async fn commit(&self, transaction: Transaction<'static, Sqlite>) -> Result<(), ApiError> {
+        transaction.commit().await.map_err(db_err)?;
+        self.publish_events().await;
+        Ok(())
     }
 }
 
+async fn fetch_resource(
+    executor: impl SqliteExecutor<'_>,
+    id: &str,
+) -> Result<Option<Resource>, ApiError> {
+    sqlx::query(
+        "SELECT id, name, description, created_at, updated_at, version, deleted_at FROM resources WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(executor)
+    .await
+    .map_err(db_err)
+    .map(|row| row.map(row_to_resource))
+}
+
+async fn fetch_live_resource(
+    connection: &mut SqliteConnection,
+    id: &str,
+    expected_version: Option<i64>,
+) -> Result<Option<Resource>, ApiError> {
+    let resource = fetch_resource(&mut *connection, id)
+        .await?
+        .filter(|resource| resource.deleted_at.is_none());
+    if let (Some(resource), Some(expected)) = (&resource, expected_version)
+        && resource.version != expected
+    {
+        return Err(ApiError::PreconditionFailed);
+    }
+    Ok(resource)
+}
+
+async fn insert(
+    connection: &mut SqliteConnection,
+    input: CreateResource,
+    context: &RequestContext,
+) -> Result<Resource, ApiError> {
+    insert_with_id(connection, generate_id(), input, context).await
+}
+
+async fn insert_with_id(
+    connection: &mut SqliteConnection,
+    id: String,
+    input: CreateResource,
+    context: &RequestContext,
+) -> Result<Resource, ApiError> {
+    let now = timestamp(Utc::now());
+    sqlx::query("INSERT INTO resources (id, name, description, created_at, updated_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(&id)
+        .bind(&input.name)
+        .bind(&input.description)
+        .bind(&now)
+        .bind(&now)
+        .execute(&mut *connection)
+        .await
+        .map_err(db_err)?;
+    let resource = fetch_resource(&mut *connection, &id)
+        .await?
+        .ok_or_else(|| ApiError::Database("Insert failed".to_string()))?;
+    audit::record(
+        connection,
+        context,
+        &id,
+        AuditAction::Created,
+        None,
+        Some(&resource),
+    )
+    .await?;
+    revisions::record(connection, context, &resource).await?;
+    Ok(resource)
+}
+
+async fn update(
+    connection: &mut SqliteConnection,
+    id: &str,
+    input: UpdateResource,
+    expected_version: Option<i64>,
+    context: &RequestContext,
+) -> Result<Option<Resource>, ApiError> {
+    let Some(before) = fetch_live_resource(connection, id, expected_version).await? else {
+        return Ok(None);
+    };
+    sqlx::query(
+        "UPDATE resources SET
+            name = CASE WHEN ? THEN ? ELSE name END,
+            description = CASE WHEN ? THEN ? ELSE description END,
+            updated_at = ?,
+            version = version + 1
+        WHERE id = ?",
+    )
+    .bind(!input.name.is_unchanged())
+    .bind(input.name.value())
+    .bind(!input.description.is_unchanged())
+    .bind(input.description.value())
+    .bind(timestamp(Utc::now()))
+    .bind(id)
+    .execute(&mut *connection)
+    .await
+    .map_err(db_err)?;
+    let after = fetch_resource(&mut *connection, id).await?;
+    audit::record(
+        connection,
+        context,
+        id,
+        AuditAction::Updated,
+        Some(&before),
+        after.as_ref(),
+    )
+    .await?;
+    if let Some(after) = &after {
+        revisions::record(connection, context, after).await?;
+    }
+    Ok(after)
+}
+
+async fn soft_delete(
+    connection: &mut SqliteConnection,
+    id: &str,
+    expected_version: Option<i64>,
+    context: &RequestContext,
+) -> Result<bool, ApiError> {
+    let Some(before) = fetch_live_resource(connection, id, expected_version).await? else {
+        return Ok(false);
+    };
+    let now = timestamp(Utc::now());
+    sqlx::query(
+        "UPDATE resources SET deleted_at = ?, updated_at = ?, version = version + 1 WHERE id = ?",
+    )
+    .bind(&now)
+    .bind(&now)
+    .bind(id)
+    .execute(&mut *connection)
+    .await
+    .map_err(db_err)?;
+    let after = fetch_resource(&mut *connection, id).await?;
+    audit::record(
+        connection,
+        context,
+        id,
+        AuditAction::Deleted,
+        Some(&before),
+        after.as_ref(),
+    )
+    .await?;
+    if let Some(after) = &after {
+        revisions::record(connection, context, after).await?;
+    }
+    Ok(true)
+}
+
+async fn purge(
+    connection: &mut SqliteConnection,
+    resource: &Resource,
+    context: &RequestContext,
+) -> Result<(), ApiError> {
+    sqlx::query("DELETE FROM resources WHERE id = ?")
+        .bind(&resource.id)
+        .execute(&mut *connection)
+        .await
+        .map_err(db_err)?;
+    sqlx::query("DELETE FROM resource_revisions WHERE resource_id = ?")
+        .bind(&resource.id)
+        .execute(&mut *connection)
+        .await
+        .map_err(db_err)?;
+    audit::record(
+        connection,
+        context,
+        &resource.id,
+        AuditAction::Purged,
+        Some(resource),
+        None,
+    )
+    .await
+}
+
+fn fts_expression(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+fn push_filters(builder: &mut QueryBuilder<'_, Sqlite>, query: &ListResourcesQuery, trashed: bool) {
+    builder.push(if trashed {
+        " AND deleted_at IS NOT NULL"
+    } else {
+        " AND deleted_at IS NULL"
+    });
+    if let Some(prefix) = query
+        .name_prefix
+        .as_deref()
+        .filter(|prefix| !prefix.is_empty())
+    {
+        builder
+            .push(" AND name LIKE ")
+            .push_bind(format!("{}%", escape_like(prefix)))
+            .push(" ESCAPE '\\'");
+    }
+    if let Some(after) = &query.created_after {
+        builder
+            .push(" AND created_at >= ")
+            .push_bind(timestamp(*after));
+    }
+    if let Some(before) = &query.created_before {
+        builder
+            .push(" AND created_at <= ")
+            .push_bind(timestamp(*before));
+    }
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+fn sort_column(sort: ResourceSort) -> &'static str {
+    match sort {
+        ResourceSort::Name => "name",
+        ResourceSort::CreatedAt => "created_at",
+        ResourceSort::UpdatedAt => "updated_at",
+    }
+}
+
+fn sort_value(resource: &Resource, sort: ResourceSort) -> String {
+    match sort {
+        ResourceSort::Name => resource.name.clone(),
+        ResourceSort::CreatedAt => timestamp(resource.created_at),
+        ResourceSort::UpdatedAt => timestamp(resource.updated_at),
+    }
+}
+
+const CURSOR_SEPARATOR: char = '\u{1f}';
+
+fn cursor_scope(sort: ResourceSort, order: SortOrder) -> String {
+    let order = match order {
+        SortOrder::Asc => "asc",
+        SortOrder::Desc => "desc",
+    };
+    format!("{}:{order}", sort_column(sort))
+}
+
+fn encode_cursor(sort: ResourceSort, order: SortOrder, value: &str, id: &str) -> String {
+    let scope = cursor_scope(sort, order);
+    format!("{scope}{CURSOR_SEPARATOR}{value}{CURSOR_SEPARATOR}{id}")
+        .bytes()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn decode_cursor(
+    cursor: &str,
+    sort: ResourceSort,
+    order: SortOrder,
+) -> Result<(String, String), ApiError> {
+    let invalid = || ApiError::BadRequest("Invalid cursor".to_string());
+    if !cursor.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(cursor.get(index..index + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(invalid)?;
+    let decoded = String::from_utf8(bytes).map_err(|_| invalid())?;
+    let (scope, rest) = decoded.split_once(CURSOR_SEPARATOR).ok_or_else(invalid)?;
+    let (value, id) = rest.rsplit_once(CURSOR_SEPARATOR).ok_or_else(invalid)?;
+    if scope != cursor_scope(sort, order) {
+        return Err(ApiError::BadRequest(
+            "Cursor was issued for a different sort or order".to_string(),
+        ));
+    }
+    Ok((value.to_string(), id.to_string()))
+}
+
 fn generate_id() -> String {
-    let duration = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-    format!("{:x}{:x}", duration.as_secs(), duration.subsec_nanos())
+    Uuid::now_v7().to_string()
 }
 
-fn now_secs() -> String {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
-        .to_string()
+fn timestamp(value: DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use api_types::events::ResourceEventKind;
+    use api_types::patch::Patch;
+    use api_types::revisions::RevisionQuery;
+
+    async fn database_with(names: &[&str]) -> SqliteDatabase {
+        let database = SqliteDatabase::new(&DatabaseConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+            ..DatabaseConfig::default()
+        })
+        .await
+        .unwrap();
+        database.migrate().await.unwrap();
+        for name in names {
+            database
+                .create_resource(
+                    CreateResource {
+                        name: name.to_string(),
+                        description: None,
+                    },
+                    &RequestContext::system(),
+                )
+                .await
+                .unwrap();
+        }
+        database
+    }
+
+    async fn collect_pages(database: &SqliteDatabase, query: ListResourcesQuery) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = database
+                .list_resources(
+                    &ListResourcesQuery {
+                        cursor: cursor.take(),
+                        ..query.clone()
+                    },
+                    false,
+                )
+                .await
+                .unwrap();
+            assert_eq!(page.total, 5);
+            names.extend(page.data.into_iter().map(|resource| resource.name));
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return names,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn keyset_pages_cover_every_row_once_in_order() {
+        let database = database_with(&["delta", "alpha", "echo", "charlie", "bravo"]).await;
+        let query = ListResourcesQuery {
+            limit: Some(2),
+            sort: ResourceSort::Name,
+            ..ListResourcesQuery::default()
+        };
+        let descending = collect_pages(&database, query.clone()).await;
+        assert_eq!(descending, ["echo", "delta", "charlie", "bravo", "alpha"]);
+        let ascending = collect_pages(
+            &database,
+            ListResourcesQuery {
+                order: SortOrder::Asc,
+                ..query
+            },
+        )
+        .await;
+        assert_eq!(ascending, ["alpha", "bravo", "charlie", "delta", "echo"]);
+    }
+
+    #[tokio::test]
+    async fn every_version_has_a_revision() {
+        let database = database_with(&["alpha"]).await;
+        let context = RequestContext::system();
+        let id = database
+            .list_resources(&ListResourcesQuery::default(), false)
+            .await
+            .unwrap()
+            .data[0]
+            .id
+            .clone();
+        assert!(database.delete_resource(&id, None, &context).await.unwrap());
+        database.restore_resource(&id, &context).await.unwrap();
+        let resource = database
+            .update_resource(
+                &id,
+                UpdateResource {
+                    name: Patch::Set("beta".to_string()),
+                    description: Patch::Unchanged,
+                },
+                None,
+                &context,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(resource.version, 4);
+        let revisions = database
+            .list_revisions(&id, &RevisionQuery::default())
+            .await
+            .unwrap();
+        let numbers: Vec<i64> = revisions.data.iter().map(|rev| rev.revision).collect();
+        assert_eq!(numbers, [4, 3, 2, 1]);
+
+        let mut connection = database.pool.acquire().await.unwrap();
+        assert!(
+            revisions::record(&mut connection, &context, &resource)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn cursor_is_rejected_for_a_different_sort_or_order() {
+        let database = database_with(&["alpha", "bravo", "charlie"]).await;
+        let query = ListResourcesQuery {
+            limit: Some(1),
+            sort: ResourceSort::Name,
+            ..ListResourcesQuery::default()
+        };
+        let cursor = database
+            .list_resources(&query, false)
+            .await
+            .unwrap()
+            .next_cursor
+            .unwrap();
+        for (sort, order) in [
+            (ResourceSort::CreatedAt, SortOrder::Desc),
+            (ResourceSort::Name, SortOrder::Asc),
+        ] {
+            let result = database
+                .list_resources(
+                    &ListResourcesQuery {
+                        cursor: Some(cursor.clone()),
+                        sort,
+                        order,
+                        ..query.clone()
+                    },
+                    false,
+                )
+                .await;
+            assert!(matches!(result, Err(ApiError::BadRequest(_))));
+        }
+        assert!(matches!(
+            decode_cursor("zz", ResourceSort::Name, SortOrder::Desc),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn export_pages_through_every_live_resource_once() {
+        let names: Vec<String> = (0..=transfer::EXPORT_PAGE_SIZE)
+            .map(|index| format!("resource {index}"))
+            .collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let database = database_with(&names).await;
+        let trashed = database
+            .list_resources(&ListResourcesQuery::default(), false)
+            .await
+            .unwrap()
+            .data[0]
+            .id
+            .clone();
+        database
+            .delete_resource(&trashed, None, &RequestContext::system())
+            .await
+            .unwrap();
+
+        let mut resources = database.stream_resources();
+        let mut ids = Vec::new();
+        while let Some(resource) = resources.recv().await {
+            ids.push(resource.unwrap().id);
+        }
+        assert_eq!(ids.len(), names.len() - 1);
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(!ids.contains(&trashed));
+    }
+
+    #[tokio::test]
+    async fn restore_and_purge_emit_their_own_event_kinds() {
+        let database = database_with(&["lifecycle"]).await;
+        let context = RequestContext::system();
+        let id = database
+            .list_resources(&ListResourcesQuery::default(), false)
+            .await
+            .unwrap()
+            .data[0]
+            .id
+            .clone();
+        database.delete_resource(&id, None, &context).await.unwrap();
+        database.restore_resource(&id, &context).await.unwrap();
+        database.delete_resource(&id, None, &context).await.unwrap();
+        database.purge_resource(&id, &context).await.unwrap();
+        let kinds: Vec<ResourceEventKind> = database
+            .events_since(0, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|event| event.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ResourceEventKind::Created,
+                ResourceEventKind::Deleted,
+                ResourceEventKind::Restored,
+                ResourceEventKind::Deleted,
+                ResourceEventKind::Purged,
+            ]
+        );
+    }
 }
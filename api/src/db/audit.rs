@@ -0,0 +1,146 @@
+use super::{SqliteDatabase, db_err, timestamp, webhooks};
+use crate::context::RequestContext;
+use crate::error::ApiError;
+use api_types::audit::{AuditAction, AuditEntry, AuditQuery};
+use api_types::events::ResourceEvent;
+use api_types::resources::Resource;
+use api_types::responses::ApiListResponse;
+use chrono::{SubsecRound, Utc};
+use sqlx::sqlite::{Sqlite, SqliteConnection, SqliteRow};
+use sqlx::{QueryBuilder, Row};
+
+pub(super) fn row_to_entry(row: SqliteRow) -> Result<AuditEntry, ApiError> {
+    let action: String = row.get("action");
+    let before: Option<String> = row.get("before");
+    let after: Option<String> = row.get("after");
+    Ok(AuditEntry {
+        id: row.get("id"),
+        resource_id: row.get("resource_id"),
+        action: action.parse().map_err(ApiError::Database)?,
+        actor: row.get("actor"),
+        request_id: row.get("request_id"),
+        created_at: row.get("created_at"),
+        before: before.as_deref().map(parse_snapshot).transpose()?,
+        after: after.as_deref().map(parse_snapshot).transpose()?,
+    })
+}
+
+fn parse_snapshot(value: &str) -> Result<serde_json::Value, ApiError> {
+    serde_json::from_str(value).map_err(|error| ApiError::Database(error.to_string()))
+}
+
+fn snapshot(resource: Option<&Resource>) -> Result<Option<String>, ApiError> {
+    resource
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|error| ApiError::Database(error.to_string()))
+}
+
+pub(super) async fn record(
+    connection: &mut SqliteConnection,
+    context: &RequestContext,
+    resource_id: &str,
+    action: AuditAction,
+    before: Option<&Resource>,
+    after: Option<&Resource>,
+) -> Result<(), ApiError> {
+    let now = Utc::now().trunc_subsecs(6);
+    let id = sqlx::query(
+        "INSERT INTO audit_log (resource_id, action, actor, request_id, created_at, before, after)
+        VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(resource_id)
+    .bind(action.as_str())
+    .bind(&context.actor)
+    .bind(&context.request_id)
+    .bind(timestamp(now))
+    .bind(snapshot(before)?)
+    .bind(snapshot(after)?)
+    .execute(&mut *connection)
+    .await
+    .map_err(db_err)?
+    .last_insert_rowid();
+    let event = ResourceEvent {
+        id,
+        kind: action.into(),
+        resource_id: resource_id.to_string(),
+        resource: after.or(before).cloned(),
+        actor: context.actor.clone(),
+        occurred_at: now,
+    };
+    webhooks::enqueue(connection, &event).await
+}
+
+impl SqliteDatabase {
+    #[tracing::instrument(skip_all)]
+    pub async fn list_audit_entries(
+        &self,
+        query: &AuditQuery,
+    ) -> Result<ApiListResponse<AuditEntry>, ApiError> {
+        let limit = query.effective_limit();
+
+        let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM audit_log WHERE 1 = 1");
+        push_filters(&mut count, query);
+        let total: i64 = count
+            .build_query_scalar()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(db_err)?;
+
+        let mut select = QueryBuilder::<Sqlite>::new(
+            "SELECT id, resource_id, action, actor, request_id, created_at, before, after
+            FROM audit_log WHERE 1 = 1",
+        );
+        push_filters(&mut select, query);
+        if let Some(cursor) = &query.cursor {
+            let before_id: i64 = cursor
+                .parse()
+                .map_err(|_| ApiError::BadRequest("Invalid cursor".to_string()))?;
+            select.push(" AND id < ").push_bind(before_id);
+        }
+        select
+            .push(" ORDER BY id DESC LIMIT ")
+            .push_bind(i64::from(limit) + 1);
+
+        let rows = select.build().fetch_all(&self.pool).await.map_err(db_err)?;
+        let mut data = rows
+            .into_iter()
+            .map(row_to_entry)
+            .collect::<Result<Vec<_>, _>>()?;
+        let next_cursor = if data.len() > limit as usize {
+            data.truncate(limit as usize);
+            data.last().map(|entry| entry.id.to_string())
+        } else {
+            None
+        };
+        Ok(ApiListResponse {
+            data,
+            total: total as usize,
+            next_cursor,
+        })
+    }
+}
+
+fn push_filters(builder: &mut QueryBuilder<'_, Sqlite>, query: &AuditQuery) {
+    if let Some(resource_id) = &query.resource_id {
+        builder
+            .push(" AND resource_id = ")
+            .push_bind(resource_id.clone());
+    }
+    if let Some(actor) = &query.actor {
+        builder.push(" AND actor = ").push_bind(actor.clone());
+    }
+    if let Some(action) = query.action {
+        builder.push(" AND action = ").push_bind(action.as_str());
+    }
+    if let Some(since) = query.since {
+        builder
+            .push(" AND created_at >= ")
+            .push_bind(timestamp(since));
+    }
+    if let Some(until) = query.until {
+        builder
+            .push(" AND created_at <= ")
+            .push_bind(timestamp(until));
+    }
+}
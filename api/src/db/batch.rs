@@ -0,0 +1,117 @@
+use super::{SqliteDatabase, db_err, insert, soft_delete, update};
+use crate::context::RequestContext;
+use crate::error::ApiError;
+use api_types::batch::{BatchItemResult, BatchItemStatus, BatchMode, BatchResponse};
+use api_types::resources::{CreateResource, Resource, UpdateResource};
+use sqlx::{Acquire, SqliteConnection};
+
+pub enum BatchOperation {
+    Create(CreateResource),
+    Update {
+        id: String,
+        expected_version: Option<i64>,
+        changes: UpdateResource,
+    },
+    Delete {
+        id: String,
+        expected_version: Option<i64>,
+    },
+}
+
+async fn apply(
+    connection: &mut SqliteConnection,
+    operation: BatchOperation,
+    context: &RequestContext,
+) -> Result<Option<Resource>, ApiError> {
+    match operation {
+        BatchOperation::Create(input) => insert(connection, input, context).await.map(Some),
+        BatchOperation::Update {
+            id,
+            expected_version,
+            changes,
+        } => update(connection, &id, changes, expected_version, context)
+            .await?
+            .ok_or(ApiError::NotFound)
+            .map(Some),
+        BatchOperation::Delete {
+            id,
+            expected_version,
+        } => {
+            if !soft_delete(connection, &id, expected_version, context).await? {
+                return Err(ApiError::NotFound);
+            }
+            Ok(None)
+        }
+    }
+}
+
+impl SqliteDatabase {
+    #[tracing::instrument(skip_all)]
+    pub async fn run_batch(
+        &self,
+        mode: BatchMode,
+        operations: Vec<Result<BatchOperation, ApiError>>,
+        context: &RequestContext,
+    ) -> Result<BatchResponse, ApiError> {
+        let mut transaction = self.begin().await?;
+        let mut results = Vec::with_capacity(operations.len());
+        for (index, operation) in operations.into_iter().enumerate() {
+            let outcome = match operation {
+                Ok(operation) => {
+                    let mut savepoint = transaction.begin().await.map_err(db_err)?;
+                    match apply(&mut savepoint, operation, context).await {
+                        Ok(data) => {
+                            savepoint.commit().await.map_err(db_err)?;
+                            Ok(data)
+                        }
+                        Err(ApiError::Database(message)) => {
+                            return Err(ApiError::Database(message));
+                        }
+                        Err(error) => {
+                            savepoint.rollback().await.map_err(db_err)?;
+                            Err(error)
+                        }
+                    }
+                }
+                Err(error) => Err(error),
+            };
+            results.push(match outcome {
+                Ok(data) => BatchItemResult {
+                    index,
+                    status: BatchItemStatus::Succeeded,
+                    data,
+                    error: None,
+                },
+                Err(error) => BatchItemResult {
+                    index,
+                    status: BatchItemStatus::Failed,
+                    data: None,
+                    error: Some(error.into_body()),
+                },
+            });
+        }
+
+        let failed = results
+            .iter()
+            .filter(|result| result.status == BatchItemStatus::Failed)
+            .count();
+        let committed = failed == 0 || mode == BatchMode::BestEffort;
+        if committed {
+            self.commit(transaction).await?;
+        } else {
+            transaction.rollback().await.map_err(db_err)?;
+            for result in &mut results {
+                if result.status == BatchItemStatus::Succeeded {
+                    result.status = BatchItemStatus::RolledBack;
+                    result.data = None;
+                }
+            }
+        }
+        Ok(BatchResponse {
+            committed,
+            succeeded: if committed { results.len() - failed } else { 0 },
+            failed,
+            results,
+        })
+    }
+}
@@ -0,0 +1,98 @@
+use super::audit::row_to_entry;
+use super::{SqliteDatabase, db_err};
+use crate::error::ApiError;
+use api_types::audit::AuditEntry;
+use api_types::events::ResourceEvent;
+use std::sync::Arc;
+use tokio::sync::{Mutex, broadcast};
+
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+const PUBLISH_PAGE_SIZE: u32 = 500;
+
+#[derive(Clone)]
+pub(super) struct EventFeed {
+    sender: broadcast::Sender<ResourceEvent>,
+    published: Arc<Mutex<i64>>,
+}
+
+impl EventFeed {
+    pub(super) fn new() -> Self {
+        Self {
+            sender: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            published: Arc::new(Mutex::new(0)),
+        }
+    }
+}
+
+fn entry_to_event(entry: AuditEntry) -> Result<ResourceEvent, ApiError> {
+    let resource = entry
+        .after
+        .or(entry.before)
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|error| ApiError::Database(error.to_string()))?;
+    Ok(ResourceEvent {
+        id: entry.id,
+        kind: entry.action.into(),
+        resource_id: entry.resource_id,
+        resource,
+        actor: entry.actor,
+        occurred_at: entry.created_at,
+    })
+}
+
+impl SqliteDatabase {
+    pub fn subscribe(&self) -> broadcast::Receiver<ResourceEvent> {
+        self.events.sender.subscribe()
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn events_since(
+        &self,
+        after: i64,
+        limit: u32,
+    ) -> Result<Vec<ResourceEvent>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT id, resource_id, action, actor, request_id, created_at, before, after
+            FROM audit_log WHERE id > ? ORDER BY id LIMIT ?",
+        )
+        .bind(after)
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_err)?;
+        rows.into_iter()
+            .map(|row| entry_to_event(row_to_entry(row)?))
+            .collect()
+    }
+
+    pub(super) async fn init_events(&self) -> Result<(), ApiError> {
+        let latest: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(id), 0) FROM audit_log")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(db_err)?;
+        *self.events.published.lock().await = latest;
+        Ok(())
+    }
+
+    pub(super) async fn publish_events(&self) {
+        let mut published = self.events.published.lock().await;
+        loop {
+            let events = match self.events_since(*published, PUBLISH_PAGE_SIZE).await {
+                Ok(events) => events,
+                Err(error) => {
+                    tracing::warn!("Failed to publish resource events: {error}");
+                    return;
+                }
+            };
+            let count = events.len();
+            for event in events {
+                *published = event.id;
+                let _ = self.events.sender.send(event);
+            }
+            if count < PUBLISH_PAGE_SIZE as usize {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use super::{MIGRATOR, SqliteDatabase, db_err};
+use crate::error::ApiError;
+use sqlx::Row;
+use std::collections::HashMap;
+
+impl SqliteDatabase {
+    #[tracing::instrument(skip_all)]
+    pub async fn ping(&self) -> Result<(), ApiError> {
+        sqlx::query("SELECT 1")
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn pending_migrations(&self) -> Result<Vec<i64>, ApiError> {
+        let applied =
+            sqlx::query("SELECT version, checksum FROM _sqlx_migrations WHERE success = 1")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(db_err)?
+                .into_iter()
+                .map(|row| (row.get("version"), row.get("checksum")))
+                .collect::<HashMap<i64, Vec<u8>>>();
+        Ok(MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .filter(|migration| {
+                applied.get(&migration.version).map(Vec::as_slice) != Some(&*migration.checksum)
+            })
+            .map(|migration| migration.version)
+            .collect())
+    }
+}
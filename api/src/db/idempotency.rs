@@ -0,0 +1,150 @@
+use super::{SqliteDatabase, db_err, timestamp};
+use crate::error::ApiError;
+use chrono::{TimeDelta, Utc};
+use sqlx::Row;
+
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub etag: Option<String>,
+    pub body: Vec<u8>,
+}
+
+pub enum IdempotencyState {
+    Started(String),
+    InProgress,
+    Mismatch,
+    Replay(StoredResponse),
+}
+
+impl SqliteDatabase {
+    #[tracing::instrument(skip_all)]
+    pub async fn begin_idempotent(
+        &self,
+        actor: &str,
+        key: &str,
+        request_hash: &str,
+        ttl: TimeDelta,
+        lease: TimeDelta,
+    ) -> Result<IdempotencyState, ApiError> {
+        let now = Utc::now();
+        sqlx::query(
+            "DELETE FROM idempotency_keys
+            WHERE expires_at < ?
+                OR (status IS NULL AND (lease_expires_at IS NULL OR lease_expires_at < ?))",
+        )
+        .bind(timestamp(now))
+        .bind(timestamp(now))
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        let inserted = sqlx::query(
+            "INSERT OR IGNORE INTO idempotency_keys
+                (actor, key, request_hash, created_at, expires_at, lease_expires_at)
+            VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(actor)
+        .bind(key)
+        .bind(request_hash)
+        .bind(timestamp(now))
+        .bind(timestamp(now + ttl))
+        .bind(timestamp(now + lease))
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?
+        .rows_affected();
+        if inserted > 0 {
+            return Ok(IdempotencyState::Started(timestamp(now)));
+        }
+        let Some(row) = sqlx::query(
+            "SELECT request_hash, status, content_type, etag, body FROM idempotency_keys
+            WHERE actor = ? AND key = ?",
+        )
+        .bind(actor)
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_err)?
+        else {
+            return Ok(IdempotencyState::InProgress);
+        };
+        let stored_hash: String = row.get("request_hash");
+        if stored_hash != request_hash {
+            return Ok(IdempotencyState::Mismatch);
+        }
+        let status: Option<i64> = row.get("status");
+        Ok(match status {
+            Some(status) => IdempotencyState::Replay(StoredResponse {
+                status: status as u16,
+                content_type: row.get("content_type"),
+                etag: row.get("etag"),
+                body: row.get::<Option<Vec<u8>>, _>("body").unwrap_or_default(),
+            }),
+            None => IdempotencyState::InProgress,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn renew_idempotent(
+        &self,
+        actor: &str,
+        key: &str,
+        started: &str,
+        lease: TimeDelta,
+    ) -> Result<(), ApiError> {
+        sqlx::query(
+            "UPDATE idempotency_keys SET lease_expires_at = ?
+            WHERE actor = ? AND key = ? AND created_at = ? AND status IS NULL",
+        )
+        .bind(timestamp(Utc::now() + lease))
+        .bind(actor)
+        .bind(key)
+        .bind(started)
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn complete_idempotent(
+        &self,
+        actor: &str,
+        key: &str,
+        started: &str,
+        response: &StoredResponse,
+    ) -> Result<(), ApiError> {
+        sqlx::query(
+            "UPDATE idempotency_keys SET status = ?, content_type = ?, etag = ?, body = ?
+            WHERE actor = ? AND key = ? AND created_at = ?",
+        )
+        .bind(response.status)
+        .bind(&response.content_type)
+        .bind(&response.etag)
+        .bind(&response.body)
+        .bind(actor)
+        .bind(key)
+        .bind(started)
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn abandon_idempotent(
+        &self,
+        actor: &str,
+        key: &str,
+        started: &str,
+    ) -> Result<(), ApiError> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE actor = ? AND key = ? AND created_at = ?")
+            .bind(actor)
+            .bind(key)
+            .bind(started)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(())
+    }
+}
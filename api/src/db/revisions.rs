@@ -0,0 +1,108 @@
+use super::{SqliteDatabase, db_err, timestamp};
+use crate::context::RequestContext;
+use crate::error::ApiError;
+use api_types::resources::Resource;
+use api_types::responses::ApiListResponse;
+use api_types::revisions::{ResourceRevision, RevisionQuery};
+use chrono::Utc;
+use sqlx::Row;
+use sqlx::sqlite::{SqliteConnection, SqliteRow};
+
+fn row_to_revision(row: SqliteRow) -> ResourceRevision {
+    ResourceRevision {
+        resource_id: row.get("resource_id"),
+        revision: row.get("revision"),
+        name: row.get("name"),
+        description: row.get("description"),
+        actor: row.get("actor"),
+        created_at: row.get("created_at"),
+    }
+}
+
+pub(super) async fn record(
+    connection: &mut SqliteConnection,
+    context: &RequestContext,
+    resource: &Resource,
+) -> Result<(), ApiError> {
+    sqlx::query(
+        "INSERT INTO resource_revisions (resource_id, revision, name, description, actor, created_at)
+        VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&resource.id)
+    .bind(resource.version)
+    .bind(&resource.name)
+    .bind(&resource.description)
+    .bind(&context.actor)
+    .bind(timestamp(Utc::now()))
+    .execute(connection)
+    .await
+    .map_err(db_err)?;
+    Ok(())
+}
+
+impl SqliteDatabase {
+    #[tracing::instrument(skip_all)]
+    pub async fn list_revisions(
+        &self,
+        resource_id: &str,
+        query: &RevisionQuery,
+    ) -> Result<ApiListResponse<ResourceRevision>, ApiError> {
+        let limit = query.effective_limit();
+        let before = query
+            .cursor
+            .as_deref()
+            .map(str::parse::<i64>)
+            .transpose()
+            .map_err(|_| ApiError::BadRequest("Invalid cursor".to_string()))?;
+        let total: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM resource_revisions WHERE resource_id = ?")
+                .bind(resource_id)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(db_err)?;
+        let rows = sqlx::query(
+            "SELECT resource_id, revision, name, description, actor, created_at
+            FROM resource_revisions
+            WHERE resource_id = ? AND (? IS NULL OR revision < ?)
+            ORDER BY revision DESC
+            LIMIT ?",
+        )
+        .bind(resource_id)
+        .bind(before)
+        .bind(before)
+        .bind(i64::from(limit) + 1)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_err)?;
+        let mut data: Vec<ResourceRevision> = rows.into_iter().map(row_to_revision).collect();
+        let next_cursor = if data.len() > limit as usize {
+            data.truncate(limit as usize);
+            data.last().map(|revision| revision.revision.to_string())
+        } else {
+            None
+        };
+        Ok(ApiListResponse {
+            data,
+            total: total as usize,
+            next_cursor,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn get_revision(
+        &self,
+        resource_id: &str,
+        revision: i64,
+    ) -> Result<Option<ResourceRevision>, ApiError> {
+        sqlx::query(
+            "SELECT resource_id, revision, name, description, actor, created_at
+            FROM resource_revisions WHERE resource_id = ? AND revision = ?",
+        )
+        .bind(resource_id)
+        .bind(revision)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_err)
+        .map(|row| row.map(row_to_revision))
+    }
+}
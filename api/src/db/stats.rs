@@ -0,0 +1,41 @@
+use super::{SqliteDatabase, db_err};
+use crate::error::ApiError;
+use sqlx::Row;
+
+pub struct PoolStats {
+    pub size: u32,
+    pub idle: u32,
+    pub max: u32,
+}
+
+pub struct ResourceCounts {
+    pub active: i64,
+    pub trashed: i64,
+}
+
+impl SqliteDatabase {
+    pub fn pool_stats(&self) -> PoolStats {
+        PoolStats {
+            size: self.pool.size(),
+            idle: self.pool.num_idle() as u32,
+            max: self.pool.options().get_max_connections(),
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn resource_counts(&self) -> Result<ResourceCounts, ApiError> {
+        let row = sqlx::query(
+            "SELECT
+                COUNT(*) FILTER (WHERE deleted_at IS NULL) AS active,
+                COUNT(*) FILTER (WHERE deleted_at IS NOT NULL) AS trashed
+            FROM resources",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(ResourceCounts {
+            active: row.get("active"),
+            trashed: row.get("trashed"),
+        })
+    }
+}
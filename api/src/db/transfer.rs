@@ -0,0 +1,139 @@
+use super::{
+    SqliteDatabase, db_err, fetch_resource, generate_id, insert_with_id, row_to_resource, update,
+};
+use crate::context::RequestContext;
+use crate::error::ApiError;
+use crate::transfer::ImportRow;
+use api_types::patch::Patch;
+use api_types::resources::{CreateResource, Resource, UpdateResource};
+use api_types::transfer::{ImportError, ImportReport};
+use sqlx::{Acquire, SqliteConnection};
+use tokio::sync::mpsc;
+
+pub(super) const EXPORT_PAGE_SIZE: i64 = 500;
+
+enum Upserted {
+    Created,
+    Updated,
+}
+
+async fn upsert(
+    connection: &mut SqliteConnection,
+    id: Option<String>,
+    input: CreateResource,
+    context: &RequestContext,
+) -> Result<Upserted, ApiError> {
+    let Some(id) = id else {
+        insert_with_id(connection, generate_id(), input, context).await?;
+        return Ok(Upserted::Created);
+    };
+    match fetch_resource(&mut *connection, &id).await? {
+        None => {
+            insert_with_id(connection, id, input, context).await?;
+            Ok(Upserted::Created)
+        }
+        Some(existing) if existing.deleted_at.is_some() => Err(ApiError::BadRequest(format!(
+            "Resource {id} is in the trash"
+        ))),
+        Some(_) => {
+            let changes = UpdateResource {
+                name: Patch::Set(input.name),
+                description: input.description.into(),
+            };
+            update(connection, &id, changes, None, context).await?;
+            Ok(Upserted::Updated)
+        }
+    }
+}
+
+impl SqliteDatabase {
+    pub fn stream_resources(&self) -> mpsc::Receiver<Result<Resource, ApiError>> {
+        let (sender, receiver) = mpsc::channel(64);
+        let pool = self.pool.clone();
+        tokio::spawn(async move {
+            let mut after = String::new();
+            loop {
+                let page = sqlx::query(
+                    "SELECT id, name, description, created_at, updated_at, version, deleted_at
+                    FROM resources WHERE deleted_at IS NULL AND id > ? ORDER BY id LIMIT ?",
+                )
+                .bind(&after)
+                .bind(EXPORT_PAGE_SIZE)
+                .fetch_all(&pool)
+                .await;
+                let rows = match page {
+                    Ok(rows) => rows,
+                    Err(error) => {
+                        let _ = sender.send(Err(db_err(error))).await;
+                        return;
+                    }
+                };
+                let last_page = rows.len() < EXPORT_PAGE_SIZE as usize;
+                for row in rows {
+                    let resource = row_to_resource(row);
+                    after.clone_from(&resource.id);
+                    if sender.send(Ok(resource)).await.is_err() {
+                        return;
+                    }
+                }
+                if last_page {
+                    return;
+                }
+            }
+        });
+        receiver
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn import_resources(
+        &self,
+        rows: Vec<ImportRow>,
+        dry_run: bool,
+        context: &RequestContext,
+    ) -> Result<ImportReport, ApiError> {
+        let mut report = ImportReport {
+            dry_run,
+            ..ImportReport::default()
+        };
+        let mut transaction = self.begin().await?;
+        for row in rows {
+            let outcome = match row.input {
+                Ok(input) => {
+                    let mut savepoint = transaction.begin().await.map_err(db_err)?;
+                    match upsert(&mut savepoint, row.id.clone(), input, context).await {
+                        Ok(upserted) => {
+                            savepoint.commit().await.map_err(db_err)?;
+                            Ok(upserted)
+                        }
+                        Err(ApiError::Database(message)) => {
+                            return Err(ApiError::Database(message));
+                        }
+                        Err(error) => {
+                            savepoint.rollback().await.map_err(db_err)?;
+                            Err(error)
+                        }
+                    }
+                }
+                Err(error) => Err(error),
+            };
+            match outcome {
+                Ok(Upserted::Created) => report.created += 1,
+                Ok(Upserted::Updated) => report.updated += 1,
+                Err(error) => {
+                    report.failed += 1;
+                    report.errors.push(ImportError {
+                        line: row.line,
+                        id: row.id,
+                        error: error.into_body(),
+                    });
+                }
+            }
+        }
+        if dry_run {
+            transaction.rollback().await.map_err(db_err)?;
+        } else {
+            self.commit(transaction).await?;
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,371 @@
+use super::{SqliteDatabase, db_err, generate_id, timestamp};
+use crate::error::ApiError;
+use api_types::events::{ResourceEvent, ResourceEventKind};
+use api_types::responses::ApiListResponse;
+use api_types::webhooks::{
+    CreateWebhook, DeliveryQuery, DeliveryStatus, UpdateWebhook, Webhook, WebhookDelivery,
+};
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::{Sqlite, SqliteConnection, SqliteRow};
+use sqlx::{QueryBuilder, Row};
+
+pub struct DueDelivery {
+    pub id: i64,
+    pub url: String,
+    pub secret: String,
+    pub event_kind: String,
+    pub payload: String,
+    pub attempts: u32,
+}
+
+fn encode_events(events: &[ResourceEventKind]) -> String {
+    events
+        .iter()
+        .map(ResourceEventKind::as_str)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn decode_events(value: &str) -> Result<Vec<ResourceEventKind>, ApiError> {
+    value
+        .split(',')
+        .filter(|kind| !kind.is_empty())
+        .map(|kind| kind.parse().map_err(ApiError::Database))
+        .collect()
+}
+
+fn row_to_webhook(row: SqliteRow) -> Result<Webhook, ApiError> {
+    let events: String = row.get("events");
+    let active: bool = row.get("active");
+    Ok(Webhook {
+        id: row.get("id"),
+        url: row.get("url"),
+        events: decode_events(&events)?,
+        active,
+        secret: None,
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+fn row_to_delivery(row: SqliteRow) -> Result<WebhookDelivery, ApiError> {
+    let event_kind: String = row.get("event_kind");
+    let status: String = row.get("status");
+    let payload: String = row.get("payload");
+    let attempts: i64 = row.get("attempts");
+    let last_status: Option<i64> = row.get("last_status");
+    Ok(WebhookDelivery {
+        id: row.get("id"),
+        webhook_id: row.get("webhook_id"),
+        event_id: row.get("event_id"),
+        event_kind: event_kind.parse().map_err(ApiError::Database)?,
+        status: status.parse().map_err(ApiError::Database)?,
+        attempts: attempts as u32,
+        next_attempt_at: row.get("next_attempt_at"),
+        last_status: last_status.map(|status| status as u16),
+        last_error: row.get("last_error"),
+        created_at: row.get("created_at"),
+        delivered_at: row.get("delivered_at"),
+        payload: serde_json::from_str(&payload)
+            .map_err(|error| ApiError::Database(error.to_string()))?,
+    })
+}
+
+fn generate_secret() -> String {
+    format!("whsec_{}", hex::encode(rand::random::<[u8; 24]>()))
+}
+
+pub(super) async fn enqueue(
+    connection: &mut SqliteConnection,
+    event: &ResourceEvent,
+) -> Result<(), ApiError> {
+    let rows = sqlx::query("SELECT id, events FROM webhooks WHERE active = 1")
+        .fetch_all(&mut *connection)
+        .await
+        .map_err(db_err)?;
+    let mut payload = None;
+    let now = timestamp(Utc::now());
+    for row in rows {
+        let events: String = row.get("events");
+        if !decode_events(&events)?.contains(&event.kind) {
+            continue;
+        }
+        if payload.is_none() {
+            payload = Some(
+                serde_json::to_string(event)
+                    .map_err(|error| ApiError::Database(error.to_string()))?,
+            );
+        }
+        let webhook_id: String = row.get("id");
+        sqlx::query(
+            "INSERT INTO webhook_deliveries (webhook_id, event_id, event_kind, payload, next_attempt_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(webhook_id)
+        .bind(event.id)
+        .bind(event.kind.as_str())
+        .bind(&payload)
+        .bind(&now)
+        .bind(&now)
+        .execute(&mut *connection)
+        .await
+        .map_err(db_err)?;
+    }
+    Ok(())
+}
+
+impl SqliteDatabase {
+    #[tracing::instrument(skip_all)]
+    pub async fn create_webhook(&self, input: CreateWebhook) -> Result<Webhook, ApiError> {
+        let id = generate_id();
+        let secret = input.secret.unwrap_or_else(generate_secret);
+        let now = timestamp(Utc::now());
+        sqlx::query(
+            "INSERT INTO webhooks (id, url, secret, events, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(&input.url)
+        .bind(&secret)
+        .bind(encode_events(&input.events))
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        let webhook = self.get_webhook(&id).await?.ok_or(ApiError::NotFound)?;
+        Ok(Webhook {
+            secret: Some(secret),
+            ..webhook
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn list_webhooks(&self) -> Result<ApiListResponse<Webhook>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT id, url, events, active, created_at, updated_at FROM webhooks ORDER BY created_at",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_err)?;
+        let data = rows
+            .into_iter()
+            .map(row_to_webhook)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ApiListResponse {
+            total: data.len(),
+            data,
+            next_cursor: None,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn get_webhook(&self, id: &str) -> Result<Option<Webhook>, ApiError> {
+        sqlx::query(
+            "SELECT id, url, events, active, created_at, updated_at FROM webhooks WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_err)?
+        .map(row_to_webhook)
+        .transpose()
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn update_webhook(
+        &self,
+        id: &str,
+        input: UpdateWebhook,
+    ) -> Result<Option<Webhook>, ApiError> {
+        let secret = input.rotate_secret.then(generate_secret);
+        let result = sqlx::query(
+            "UPDATE webhooks SET
+                url = COALESCE(?, url),
+                events = COALESCE(?, events),
+                active = COALESCE(?, active),
+                secret = COALESCE(?, secret),
+                updated_at = ?
+            WHERE id = ?",
+        )
+        .bind(&input.url)
+        .bind(input.events.as_deref().map(encode_events))
+        .bind(input.active)
+        .bind(&secret)
+        .bind(timestamp(Utc::now()))
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        Ok(self
+            .get_webhook(id)
+            .await?
+            .map(|webhook| Webhook { secret, ..webhook }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn delete_webhook(&self, id: &str) -> Result<bool, ApiError> {
+        let result = sqlx::query("DELETE FROM webhooks WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(db_err)?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn list_deliveries(
+        &self,
+        webhook_id: Option<&str>,
+        query: &DeliveryQuery,
+    ) -> Result<ApiListResponse<WebhookDelivery>, ApiError> {
+        let limit = query.effective_limit();
+        let push_filters = |builder: &mut QueryBuilder<'_, Sqlite>| {
+            if let Some(webhook_id) = webhook_id {
+                builder
+                    .push(" AND webhook_id = ")
+                    .push_bind(webhook_id.to_string());
+            }
+            if let Some(status) = query.status {
+                builder.push(" AND status = ").push_bind(status.as_str());
+            }
+        };
+
+        let mut count =
+            QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM webhook_deliveries WHERE 1 = 1");
+        push_filters(&mut count);
+        let total: i64 = count
+            .build_query_scalar()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(db_err)?;
+
+        let mut select = QueryBuilder::<Sqlite>::new(
+            "SELECT id, webhook_id, event_id, event_kind, payload, status, attempts, next_attempt_at,
+                last_status, last_error, created_at, delivered_at
+            FROM webhook_deliveries WHERE 1 = 1",
+        );
+        push_filters(&mut select);
+        if let Some(cursor) = &query.cursor {
+            let before_id: i64 = cursor
+                .parse()
+                .map_err(|_| ApiError::BadRequest("Invalid cursor".to_string()))?;
+            select.push(" AND id < ").push_bind(before_id);
+        }
+        select
+            .push(" ORDER BY id DESC LIMIT ")
+            .push_bind(i64::from(limit) + 1);
+
+        let rows = select.build().fetch_all(&self.pool).await.map_err(db_err)?;
+        let mut data = rows
+            .into_iter()
+            .map(row_to_delivery)
+            .collect::<Result<Vec<_>, _>>()?;
+        let next_cursor = if data.len() > limit as usize {
+            data.truncate(limit as usize);
+            data.last().map(|delivery| delivery.id.to_string())
+        } else {
+            None
+        };
+        Ok(ApiListResponse {
+            data,
+            total: total as usize,
+            next_cursor,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn retry_delivery(&self, id: i64) -> Result<Option<WebhookDelivery>, ApiError> {
+        let result = sqlx::query(
+            "UPDATE webhook_deliveries SET status = ?, attempts = 0, next_attempt_at = ?
+            WHERE id = ? AND status = ?",
+        )
+        .bind(DeliveryStatus::Pending.as_str())
+        .bind(timestamp(Utc::now()))
+        .bind(id)
+        .bind(DeliveryStatus::Dead.as_str())
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        sqlx::query(
+            "SELECT id, webhook_id, event_id, event_kind, payload, status, attempts, next_attempt_at,
+                last_status, last_error, created_at, delivered_at
+            FROM webhook_deliveries WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(db_err)?
+        .map(row_to_delivery)
+        .transpose()
+    }
+
+    pub async fn due_deliveries(&self, limit: u32) -> Result<Vec<DueDelivery>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT d.id, w.url, w.secret, d.event_kind, d.payload, d.attempts
+            FROM webhook_deliveries d
+            JOIN webhooks w ON w.id = d.webhook_id
+            WHERE d.status = ? AND d.next_attempt_at <= ? AND w.active = 1
+            ORDER BY d.id
+            LIMIT ?",
+        )
+        .bind(DeliveryStatus::Pending.as_str())
+        .bind(timestamp(Utc::now()))
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let attempts: i64 = row.get("attempts");
+                DueDelivery {
+                    id: row.get("id"),
+                    url: row.get("url"),
+                    secret: row.get("secret"),
+                    event_kind: row.get("event_kind"),
+                    payload: row.get("payload"),
+                    attempts: attempts as u32,
+                }
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn record_delivery_attempt(
+        &self,
+        id: i64,
+        status: DeliveryStatus,
+        response_status: Option<u16>,
+        error: Option<&str>,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), ApiError> {
+        let now = timestamp(Utc::now());
+        sqlx::query(
+            "UPDATE webhook_deliveries SET
+                status = ?,
+                attempts = attempts + 1,
+                last_status = ?,
+                last_error = ?,
+                next_attempt_at = ?,
+                delivered_at = CASE WHEN ? THEN ? ELSE delivered_at END
+            WHERE id = ?",
+        )
+        .bind(status.as_str())
+        .bind(response_status)
+        .bind(error)
+        .bind(timestamp(next_attempt_at))
+        .bind(status == DeliveryStatus::Delivered)
+        .bind(&now)
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(())
+    }
+}
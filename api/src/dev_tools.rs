@@ -0,0 +1,96 @@
+use crate::webhooks;
+use api_types::webhooks::{DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use axum::Router;
+use axum::body::Bytes;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::routing::post;
+use std::net::SocketAddr;
+
+pub async fn receive_webhooks(
+    bind: SocketAddr,
+    secret: String,
+    status: u16,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let status = StatusCode::from_u16(status)?;
+    let app = Router::new().fallback(move |headers: HeaderMap, body: String| async move {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let verified = header(TIMESTAMP_HEADER).parse().is_ok_and(|timestamp| {
+            webhooks::verify(&secret, timestamp, &body, &header(SIGNATURE_HEADER))
+        });
+        println!(
+            "delivery={} event={} verified={verified} {body}",
+            header(DELIVERY_HEADER),
+            header(EVENT_HEADER),
+        );
+        if verified {
+            status
+        } else {
+            StatusCode::UNAUTHORIZED
+        }
+    });
+    let listener = tokio::net::TcpListener::bind(bind).await?;
+    tracing::info!("Receiving webhooks on {bind}");
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+pub async fn receive_traces(bind: SocketAddr) -> Result<(), Box<dyn std::error::Error>> {
+    let app = Router::new().route(
+        "/v1/traces",
+        post(|headers: HeaderMap, body: Bytes| async move {
+            let json = headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| value.starts_with("application/json"));
+            if !json {
+                println!(
+                    "received {} bytes of protobuf; set OTEL_EXPORTER_OTLP_PROTOCOL=http/json to print spans",
+                    body.len()
+                );
+                return StatusCode::OK;
+            }
+            let Ok(payload) = serde_json::from_slice::<serde_json::Value>(&body) else {
+                return StatusCode::BAD_REQUEST;
+            };
+            let spans = payload["resourceSpans"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .flat_map(|resource| resource["scopeSpans"].as_array().into_iter().flatten())
+                .flat_map(|scope| scope["spans"].as_array().into_iter().flatten());
+            for span in spans {
+                let nanos = |field: &str| {
+                    span[field]
+                        .as_str()
+                        .and_then(|value| value.parse::<u64>().ok())
+                        .or_else(|| span[field].as_u64())
+                        .unwrap_or_default()
+                };
+                let duration_ms =
+                    nanos("endTimeUnixNano").saturating_sub(nanos("startTimeUnixNano")) as f64 / 1e6;
+                println!(
+                    "trace={} span={} parent={} {:.3}ms {}",
+                    span["traceId"].as_str().unwrap_or_default(),
+                    span["spanId"].as_str().unwrap_or_default(),
+                    span["parentSpanId"]
+                        .as_str()
+                        .filter(|parent| !parent.is_empty())
+                        .unwrap_or("-"),
+                    duration_ms,
+                    span["name"].as_str().unwrap_or_default(),
+                );
+            }
+            StatusCode::OK
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind(bind).await?;
+    tracing::info!("Receiving traces on {bind}");
+    axum::serve(listener, app).await?;
+    Ok(())
+}
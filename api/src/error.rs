@@ -1,26 +1,168 @@
+use crate::request_id;
+use api_types::responses::ApiErrorResponse;
+use api_types::validation::FieldError;
 use axum::Json;
+use axum::extract::rejection::{JsonRejection, QueryRejection};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
-use serde_json::json;
+use std::error::Error as StdError;
 
 #[derive(thiserror::Error, Debug)]
 pub enum ApiError {
     #[error("Resource not found")]
     NotFound,
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Resource was modified by another client")]
+    PreconditionFailed,
+    #[error("If-Match header is required")]
+    PreconditionRequired,
+    #[error("Request body is too large")]
+    PayloadTooLarge,
+    #[error("Expected request with `Content-Type: application/json`")]
+    UnsupportedMediaType,
+    #[error("Rate limit exceeded")]
+    RateLimited,
+    #[error("Validation failed")]
+    Validation(Vec<FieldError>),
     #[error("Database error: {0}")]
     Database(String),
 }
 
+impl From<Vec<FieldError>> for ApiError {
+    fn from(errors: Vec<FieldError>) -> Self {
+        ApiError::Validation(errors)
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            JsonRejection::JsonDataError(error) => {
+                let field_error = error
+                    .source()
+                    .and_then(StdError::source)
+                    .and_then(|source| {
+                        source.downcast_ref::<serde_path_to_error::Error<serde_json::Error>>()
+                    })
+                    .map_or_else(
+                        || FieldError::new("body", error.body_text()),
+                        |error| {
+                            let path = error.path().to_string();
+                            let field = if path == "." { "body" } else { &path };
+                            FieldError::new(field, error.inner().to_string())
+                        },
+                    );
+                ApiError::Validation(vec![field_error])
+            }
+            JsonRejection::MissingJsonContentType(_) => ApiError::UnsupportedMediaType,
+            rejection if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+                ApiError::PayloadTooLarge
+            }
+            rejection => ApiError::BadRequest(rejection.body_text()),
+        }
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::BadRequest(rejection.body_text())
+    }
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            ApiError::PreconditionRequired => StatusCode::PRECONDITION_REQUIRED,
+            ApiError::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ApiError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::NotFound => "NOT_FOUND",
+            ApiError::BadRequest(_) => "BAD_REQUEST",
+            ApiError::Forbidden(_) => "FORBIDDEN",
+            ApiError::Conflict(_) => "CONFLICT",
+            ApiError::PreconditionFailed => "PRECONDITION_FAILED",
+            ApiError::PreconditionRequired => "PRECONDITION_REQUIRED",
+            ApiError::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            ApiError::UnsupportedMediaType => "UNSUPPORTED_MEDIA_TYPE",
+            ApiError::RateLimited => "RATE_LIMITED",
+            ApiError::Validation(_) => "VALIDATION_ERROR",
+            ApiError::Database(_) => "DATABASE_ERROR",
+        }
+    }
+
+    pub fn into_body(self) -> ApiErrorResponse {
+        let error = self.to_string();
+        let code = self.code().to_string();
+        let fields = match self {
+            ApiError::Validation(fields) => fields,
+            _ => Vec::new(),
+        };
+        ApiErrorResponse {
+            error,
+            code,
+            fields,
+            request_id: request_id::current(),
+        }
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let (status, code) = match &self {
-            ApiError::NotFound => (StatusCode::NOT_FOUND, "NOT_FOUND"),
-            ApiError::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR"),
-        };
-        (
-            status,
-            Json(json!({ "error": self.to_string(), "code": code })),
-        )
-            .into_response()
+        (self.status(), Json(self.into_body())).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::ApiJson;
+    use axum::body::Body;
+    use axum::extract::{FromRequest, Request};
+    use axum::http::header;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Input {
+        name: String,
+        tags: Vec<u32>,
+    }
+
+    async fn rejection(body: &str) -> Vec<FieldError> {
+        let request = Request::builder()
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        match ApiJson::<Input>::from_request(request, &()).await {
+            Err(ApiError::Validation(errors)) => errors,
+            _ => panic!("expected a validation error for {body}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn json_data_errors_name_the_offending_field() {
+        let errors = rejection(r#"{"name": "a", "tags": [1, "two"]}"#).await;
+        assert_eq!(errors[0].field, "tags[1]");
+        assert!(errors[0].message.starts_with("invalid type: string"));
+        let errors = rejection(r#"{"tags": []}"#).await;
+        assert_eq!(errors[0].field, "body");
+        assert!(errors[0].message.starts_with("missing field `name`"));
     }
 }
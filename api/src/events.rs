@@ -0,0 +1,89 @@
+use crate::db::SqliteDatabase;
+use crate::error::ApiError;
+use api_types::events::{RESET_EVENT, ResourceEvent};
+use axum::response::sse::Event;
+use futures_util::{Stream, stream};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+const REPLAY_LIMIT: u32 = 500;
+
+pub enum Notification {
+    Event(ResourceEvent),
+    Reset,
+}
+
+pub struct Subscription {
+    receiver: broadcast::Receiver<ResourceEvent>,
+    pending: VecDeque<Notification>,
+    seen: i64,
+}
+
+impl Subscription {
+    pub async fn new(
+        database: &SqliteDatabase,
+        last_event_id: Option<i64>,
+    ) -> Result<Self, ApiError> {
+        let receiver = database.subscribe();
+        let mut seen = last_event_id.unwrap_or(i64::MIN);
+        let pending = match last_event_id {
+            Some(id) => {
+                let replay = database.events_since(id, REPLAY_LIMIT + 1).await?;
+                if replay.len() > REPLAY_LIMIT as usize {
+                    VecDeque::from([Notification::Reset])
+                } else {
+                    if let Some(event) = replay.last() {
+                        seen = event.id;
+                    }
+                    replay.into_iter().map(Notification::Event).collect()
+                }
+            }
+            None => VecDeque::new(),
+        };
+        Ok(Self {
+            receiver,
+            pending,
+            seen,
+        })
+    }
+
+    pub async fn next(&mut self) -> Option<Notification> {
+        if let Some(notification) = self.pending.pop_front() {
+            return Some(notification);
+        }
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) if event.id <= self.seen => continue,
+                Ok(event) => {
+                    self.seen = event.id;
+                    return Some(Notification::Event(event));
+                }
+                Err(RecvError::Lagged(_)) => return Some(Notification::Reset),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+pub fn sse_stream(subscription: Subscription) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(subscription, |mut subscription| async move {
+        let event = match subscription.next().await? {
+            Notification::Event(event) => to_sse(&event),
+            Notification::Reset => reset_event(),
+        };
+        Some((Ok(event), subscription))
+    })
+}
+
+fn to_sse(event: &ResourceEvent) -> Event {
+    Event::default()
+        .id(event.id.to_string())
+        .event(event.kind.as_str())
+        .json_data(event)
+        .unwrap_or_else(|_| reset_event())
+}
+
+fn reset_event() -> Event {
+    Event::default().event(RESET_EVENT).data("{}")
+}
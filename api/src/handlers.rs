@@ -1,67 +1,1034 @@
+use crate::context::RequestContext;
+use crate::db::BatchOperation;
 use crate::error::ApiError;
+use crate::events::{self, Subscription};
+use crate::extract::{ApiJson, ApiQuery};
+use crate::openapi::ApiDoc;
+use crate::rate_limit::{ClientKey, MessageLimiter};
+use crate::realtime;
 use crate::state::AppState;
-use api_types::resources::{CreateResource, Resource, UpdateResource};
-use api_types::responses::{ApiListResponse, ApiResponse, HealthResponse};
-use axum::Json;
-use axum::extract::{Path, State};
+use crate::transfer;
+use crate::webhooks;
+use api_types::audit::{AuditEntry, AuditQuery};
+use api_types::batch::{
+    BatchCreate, BatchDelete, BatchRequest, BatchResponse, BatchUpdate, MAX_BATCH_SIZE,
+};
+use api_types::events::ResourceEvent;
+use api_types::patch::Patch;
+use api_types::resources::{
+    CreateResource, ListResourcesQuery, ReplaceResource, Resource, ResourceSearchHit,
+    SearchResourcesQuery, UpdateResource,
+};
+use api_types::responses::{
+    ApiErrorResponse, ApiListResponse, ApiResponse, ComponentHealth, HealthResponse, HealthStatus,
+};
+use api_types::revisions::{ResourceRevision, RevisionDiff, RevisionDiffQuery, RevisionQuery};
+use api_types::transfer::{ExportQuery, ImportQuery, ImportRecord, ImportReport};
+use api_types::webhooks::{
+    CreateWebhook, DeliveryQuery, DeliveryStatus, UpdateWebhook, Webhook, WebhookDelivery,
+};
+use axum::body::Body;
+use axum::extract::{Path, State, WebSocketUpgrade};
+use axum::http::{HeaderMap, HeaderName, StatusCode, header};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use futures_util::{Stream, StreamExt};
+use sha2::{Digest, Sha256};
+use std::convert::Infallible;
+use std::time::{Duration, Instant};
+use utoipa::OpenApi;
+
+const LAST_EVENT_ID: &str = "last-event-id";
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
 
 type ListResult = Result<Json<ApiListResponse<Resource>>, ApiError>;
-type ItemResult = Result<Json<ApiResponse<Resource>>, ApiError>;
+type ItemResult = Result<([(HeaderName, String); 1], Json<ApiResponse<Resource>>), ApiError>;
+type WebhookResult = Result<Json<ApiResponse<Webhook>>, ApiError>;
+type DeliveryListResult = Result<Json<ApiListResponse<WebhookDelivery>>, ApiError>;
+type BatchResult = Result<(StatusCode, Json<BatchResponse>), ApiError>;
 
-pub async fn health_check() -> Json<HealthResponse> {
+pub async fn health_live() -> Json<HealthResponse> {
     Json(HealthResponse {
-        status: "ok".into(),
+        status: HealthStatus::Ok,
         version: env!("CARGO_PKG_VERSION").into(),
+        components: Vec::new(),
     })
 }
 
-pub async fn list_resources(State(state): State<AppState>) -> ListResult {
-    let data = state.database.list_resources().await?;
-    Ok(Json(ApiListResponse {
-        total: data.len(),
-        data,
-    }))
+pub async fn health_ready(State(state): State<AppState>) -> (StatusCode, Json<HealthResponse>) {
+    let timeout = Duration::from_millis(state.config.health_check_timeout_ms);
+    let components = vec![
+        check_component("database", timeout, state.database.ping()).await,
+        check_component("migrations", timeout, async {
+            match state.database.pending_migrations().await?.as_slice() {
+                [] => Ok(()),
+                pending => Err(ApiError::Database(format!(
+                    "Pending migrations: {}",
+                    pending
+                        .iter()
+                        .map(i64::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))),
+            }
+        })
+        .await,
+    ];
+    let status = if components
+        .iter()
+        .all(|component| component.status == HealthStatus::Ok)
+    {
+        HealthStatus::Ok
+    } else {
+        HealthStatus::Degraded
+    };
+    let code = match status {
+        HealthStatus::Ok => StatusCode::OK,
+        HealthStatus::Degraded => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (
+        code,
+        Json(HealthResponse {
+            status,
+            version: env!("CARGO_PKG_VERSION").into(),
+            components,
+        }),
+    )
+}
+
+async fn check_component(
+    name: &str,
+    timeout: Duration,
+    check: impl Future<Output = Result<(), ApiError>>,
+) -> ComponentHealth {
+    let started = Instant::now();
+    let result = tokio::time::timeout(timeout, check)
+        .await
+        .unwrap_or_else(|_| Err(ApiError::Database("Timed out".to_string())));
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    if let Err(error) = &result {
+        tracing::warn!("Readiness check `{name}` failed: {error}");
+    }
+    ComponentHealth {
+        name: name.to_string(),
+        status: if result.is_ok() {
+            HealthStatus::Ok
+        } else {
+            HealthStatus::Degraded
+        },
+        latency_ms,
+        error: result.err().map(|error| error.to_string()),
+    }
+}
+
+pub async fn openapi_spec() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+pub async fn metrics(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Some(token) = &state.config.metrics_token {
+        let presented = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .unwrap_or_default();
+        if Sha256::digest(presented) != Sha256::digest(token) {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+    }
+    let resources = state
+        .database
+        .resource_counts()
+        .await
+        .inspect_err(|error| tracing::warn!("Failed to count resources for metrics: {error}"))
+        .ok();
+    (
+        [(header::CONTENT_TYPE, METRICS_CONTENT_TYPE)],
+        state.metrics.render(state.database.pool_stats(), resources),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/resources",
+    tag = "resources",
+    params(
+        ListResourcesQuery
+    ),
+    responses(
+        (status = 200, description = "Active resources", body = ApiListResponse<Resource>)
+    )
+)]
+pub async fn list_resources(
+    State(state): State<AppState>,
+    ApiQuery(query): ApiQuery<ListResourcesQuery>,
+) -> ListResult {
+    Ok(Json(state.database.list_resources(&query, false).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/resources/trash",
+    tag = "trash",
+    params(
+        ListResourcesQuery
+    ),
+    responses(
+        (status = 200, description = "Deleted resources", body = ApiListResponse<Resource>)
+    )
+)]
+pub async fn list_trash(
+    State(state): State<AppState>,
+    ApiQuery(query): ApiQuery<ListResourcesQuery>,
+) -> ListResult {
+    Ok(Json(state.database.list_resources(&query, true).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/resources/search",
+    tag = "resources",
+    params(
+        SearchResourcesQuery
+    ),
+    responses(
+        (status = 200, description = "Ranked matches", body = ApiListResponse<ResourceSearchHit>),
+        (status = 429, description = "Rate limit exceeded", body = ApiErrorResponse)
+    )
+)]
+pub async fn search_resources(
+    State(state): State<AppState>,
+    ApiQuery(query): ApiQuery<SearchResourcesQuery>,
+) -> Result<Json<ApiListResponse<ResourceSearchHit>>, ApiError> {
+    Ok(Json(state.database.search_resources(&query).await?))
 }
 
+#[utoipa::path(
+    get,
+    path = "/resources/events",
+    tag = "events",
+    params(
+        ("last-event-id" = Option<i64>, Header, description = "Replay events after this id")
+    ),
+    responses(
+        (status = 200, description = "Server-sent stream of resource events", content_type = "text/event-stream", body = ResourceEvent),
+        (status = 400, description = "Invalid Last-Event-ID", body = ApiErrorResponse)
+    )
+)]
+pub async fn resource_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let last_event_id = headers
+        .get(LAST_EVENT_ID)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.trim().parse::<i64>().ok())
+                .ok_or_else(|| ApiError::BadRequest("Invalid Last-Event-ID header".to_string()))
+        })
+        .transpose()?;
+    let subscription = Subscription::new(&state.database, last_event_id).await?;
+    Ok(
+        Sse::new(events::sse_stream(subscription).take_until(state.shutdown.cancelled_owned()))
+            .keep_alive(KeepAlive::default()),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/ws",
+    tag = "events",
+    responses(
+        (status = 101, description = "WebSocket upgrade for resource operations and change notifications")
+    )
+)]
+pub async fn websocket(
+    State(state): State<AppState>,
+    context: RequestContext,
+    Extension(limiter): Extension<MessageLimiter>,
+    ClientKey(client): ClientKey,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    upgrade.on_upgrade(move |socket| {
+        realtime::serve(socket, state, context, realtime::Limits { limiter, client })
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/resources/{id}",
+    tag = "resources",
+    params(
+        ("id" = String, Path, description = "Resource id")
+    ),
+    responses(
+        (status = 200, description = "The resource", body = ApiResponse<Resource>, headers(("etag" = String, description = "Resource version"))),
+        (status = 404, description = "Not found", body = ApiErrorResponse)
+    )
+)]
 pub async fn get_resource(State(state): State<AppState>, Path(id): Path<String>) -> ItemResult {
-    Ok(Json(ApiResponse {
-        data: state
+    item_response(
+        state
             .database
             .get_resource(&id)
             .await?
             .ok_or(ApiError::NotFound)?,
-    }))
+    )
 }
 
+#[utoipa::path(
+    post,
+    path = "/resources",
+    tag = "resources",
+    params(
+        ("idempotency-key" = Option<String>, Header, description = "Replays the stored response when the same key is reused")
+    ),
+    request_body = CreateResource,
+    responses(
+        (status = 200, description = "The resource", body = ApiResponse<Resource>, headers(("etag" = String, description = "Resource version"))),
+        (status = 422, description = "Validation failed", body = ApiErrorResponse)
+    )
+)]
 pub async fn create_resource(
     State(state): State<AppState>,
-    Json(input): Json<CreateResource>,
+    context: RequestContext,
+    ApiJson(input): ApiJson<CreateResource>,
 ) -> ItemResult {
-    Ok(Json(ApiResponse {
-        data: state.database.create_resource(input).await?,
-    }))
+    item_response(
+        state
+            .database
+            .create_resource(input.validate()?, &context)
+            .await?,
+    )
 }
 
-pub async fn update_resource(
+#[utoipa::path(
+    put,
+    path = "/resources/{id}",
+    tag = "resources",
+    params(
+        ("id" = String, Path, description = "Resource id"),
+        ("if-match" = Option<String>, Header, description = "Expected version as an ETag; required when If-Match enforcement is enabled")
+    ),
+    request_body = CreateResource,
+    responses(
+        (status = 200, description = "The resource", body = ApiResponse<Resource>, headers(("etag" = String, description = "Resource version"))),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 412, description = "Version mismatch", body = ApiErrorResponse),
+        (status = 428, description = "If-Match header is required", body = ApiErrorResponse),
+        (status = 422, description = "Validation failed", body = ApiErrorResponse)
+    )
+)]
+pub async fn replace_resource(
     State(state): State<AppState>,
     Path(id): Path<String>,
-    Json(input): Json<UpdateResource>,
+    context: RequestContext,
+    headers: HeaderMap,
+    ApiJson(input): ApiJson<ReplaceResource>,
 ) -> ItemResult {
+    let input = UpdateResource::from(input.validate()?);
+    let expected_version = expected_version(&state, &id, &headers).await?;
+    item_response(
+        state
+            .database
+            .update_resource(&id, input, expected_version, &context)
+            .await?
+            .ok_or(ApiError::NotFound)?,
+    )
+}
+
+#[utoipa::path(
+    patch,
+    path = "/resources/{id}",
+    tag = "resources",
+    params(
+        ("id" = String, Path, description = "Resource id"),
+        ("if-match" = Option<String>, Header, description = "Expected version as an ETag; required when If-Match enforcement is enabled")
+    ),
+    request_body = UpdateResource,
+    responses(
+        (status = 200, description = "The resource", body = ApiResponse<Resource>, headers(("etag" = String, description = "Resource version"))),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 412, description = "Version mismatch", body = ApiErrorResponse),
+        (status = 428, description = "If-Match header is required", body = ApiErrorResponse),
+        (status = 422, description = "Validation failed", body = ApiErrorResponse)
+    )
+)]
+pub async fn patch_resource(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    context: RequestContext,
+    headers: HeaderMap,
+    ApiJson(input): ApiJson<UpdateResource>,
+) -> ItemResult {
+    let input = input.validate()?;
+    let expected_version = expected_version(&state, &id, &headers).await?;
+    item_response(
+        state
+            .database
+            .update_resource(&id, input, expected_version, &context)
+            .await?
+            .ok_or(ApiError::NotFound)?,
+    )
+}
+
+#[utoipa::path(
+    delete,
+    path = "/resources/{id}",
+    tag = "resources",
+    params(
+        ("id" = String, Path, description = "Resource id"),
+        ("if-match" = Option<String>, Header, description = "Expected version as an ETag; required when If-Match enforcement is enabled")
+    ),
+    responses(
+        (status = 200, description = "Moved to trash"),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 412, description = "Version mismatch", body = ApiErrorResponse),
+        (status = 428, description = "If-Match header is required", body = ApiErrorResponse)
+    )
+)]
+pub async fn delete_resource(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    context: RequestContext,
+    headers: HeaderMap,
+) -> Result<(), ApiError> {
+    let expected_version = expected_version(&state, &id, &headers).await?;
+    if !state
+        .database
+        .delete_resource(&id, expected_version, &context)
+        .await?
+    {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/resources/{id}/restore",
+    tag = "trash",
+    params(
+        ("id" = String, Path, description = "Resource id"),
+        ("idempotency-key" = Option<String>, Header, description = "Replays the stored response when the same key is reused")
+    ),
+    responses(
+        (status = 200, description = "The resource", body = ApiResponse<Resource>, headers(("etag" = String, description = "Resource version"))),
+        (status = 404, description = "Not found", body = ApiErrorResponse)
+    )
+)]
+pub async fn restore_resource(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    context: RequestContext,
+) -> ItemResult {
+    item_response(
+        state
+            .database
+            .restore_resource(&id, &context)
+            .await?
+            .ok_or(ApiError::NotFound)?,
+    )
+}
+
+#[utoipa::path(
+    delete,
+    path = "/resources/trash/{id}",
+    tag = "trash",
+    params(
+        ("id" = String, Path, description = "Resource id")
+    ),
+    responses(
+        (status = 200, description = "Permanently deleted"),
+        (status = 404, description = "Not found", body = ApiErrorResponse)
+    )
+)]
+pub async fn purge_resource(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    context: RequestContext,
+) -> Result<(), ApiError> {
+    if !state.database.purge_resource(&id, &context).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/resources/{id}/history",
+    tag = "audit",
+    params(
+        ("id" = String, Path, description = "Resource id"),
+        AuditQuery
+    ),
+    responses(
+        (status = 200, description = "Audit entries for the resource", body = ApiListResponse<AuditEntry>)
+    )
+)]
+pub async fn resource_history(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ApiQuery(query): ApiQuery<AuditQuery>,
+) -> Result<Json<ApiListResponse<AuditEntry>>, ApiError> {
+    let query = AuditQuery {
+        resource_id: Some(id),
+        ..query
+    };
+    Ok(Json(state.database.list_audit_entries(&query).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/audit",
+    tag = "audit",
+    params(
+        AuditQuery
+    ),
+    responses(
+        (status = 200, description = "Audit entries", body = ApiListResponse<AuditEntry>),
+        (status = 403, description = "Caller is not listed in admin_actors", body = ApiErrorResponse)
+    )
+)]
+pub async fn list_audit_entries(
+    State(state): State<AppState>,
+    ApiQuery(query): ApiQuery<AuditQuery>,
+) -> Result<Json<ApiListResponse<AuditEntry>>, ApiError> {
+    Ok(Json(state.database.list_audit_entries(&query).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/resources/{id}/revisions",
+    tag = "revisions",
+    params(
+        ("id" = String, Path, description = "Resource id"),
+        RevisionQuery
+    ),
+    responses(
+        (status = 200, description = "Revisions, newest first", body = ApiListResponse<ResourceRevision>),
+        (status = 404, description = "Not found", body = ApiErrorResponse)
+    )
+)]
+pub async fn list_revisions(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ApiQuery(query): ApiQuery<RevisionQuery>,
+) -> Result<Json<ApiListResponse<ResourceRevision>>, ApiError> {
+    state
+        .database
+        .get_resource(&id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(state.database.list_revisions(&id, &query).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/resources/{id}/revisions/diff",
+    tag = "revisions",
+    params(
+        ("id" = String, Path, description = "Resource id"),
+        RevisionDiffQuery
+    ),
+    responses(
+        (status = 200, description = "Field changes between two revisions", body = ApiResponse<RevisionDiff>),
+        (status = 404, description = "Not found", body = ApiErrorResponse)
+    )
+)]
+pub async fn diff_revisions(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ApiQuery(query): ApiQuery<RevisionDiffQuery>,
+) -> Result<Json<ApiResponse<RevisionDiff>>, ApiError> {
+    let from = state
+        .database
+        .get_revision(&id, query.from)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let to = state
+        .database
+        .get_revision(&id, query.to)
+        .await?
+        .ok_or(ApiError::NotFound)?;
     Ok(Json(ApiResponse {
-        data: state
+        data: RevisionDiff::between(&from, &to),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/resources/{id}/revisions/{revision}/restore",
+    tag = "revisions",
+    params(
+        ("id" = String, Path, description = "Resource id"),
+        ("revision" = i64, Path, description = "Revision number"),
+        ("if-match" = Option<String>, Header, description = "Expected version as an ETag; required when If-Match enforcement is enabled"),
+        ("idempotency-key" = Option<String>, Header, description = "Replays the stored response when the same key is reused")
+    ),
+    responses(
+        (status = 200, description = "The resource", body = ApiResponse<Resource>, headers(("etag" = String, description = "Resource version"))),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 412, description = "Version mismatch", body = ApiErrorResponse),
+        (status = 428, description = "If-Match header is required", body = ApiErrorResponse)
+    )
+)]
+pub async fn restore_revision(
+    State(state): State<AppState>,
+    Path((id, revision)): Path<(String, i64)>,
+    context: RequestContext,
+    headers: HeaderMap,
+) -> ItemResult {
+    let revision = state
+        .database
+        .get_revision(&id, revision)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let expected_version = expected_version(&state, &id, &headers).await?;
+    let input = UpdateResource {
+        name: Patch::Set(revision.name),
+        description: revision.description.into(),
+    };
+    item_response(
+        state
             .database
-            .update_resource(&id, input)
+            .update_resource(&id, input, expected_version, &context)
             .await?
             .ok_or(ApiError::NotFound)?,
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/resources/batch",
+    tag = "batch",
+    params(
+        ("idempotency-key" = Option<String>, Header, description = "Replays the stored response when the same key is reused")
+    ),
+    request_body = BatchRequest<CreateResource>,
+    responses(
+        (status = 200, description = "Batch committed", body = BatchResponse),
+        (status = 422, description = "Batch rolled back", body = BatchResponse)
+    )
+)]
+pub async fn batch_create(
+    State(state): State<AppState>,
+    context: RequestContext,
+    ApiJson(request): ApiJson<BatchRequest<BatchCreate>>,
+) -> BatchResult {
+    run_batch(&state, &context, request, |item| {
+        Ok(BatchOperation::Create(item.validate()?))
+    })
+    .await
+}
+
+#[utoipa::path(
+    patch,
+    path = "/resources/batch",
+    tag = "batch",
+    params(
+        ("idempotency-key" = Option<String>, Header, description = "Replays the stored response when the same key is reused")
+    ),
+    request_body = BatchRequest<BatchUpdate>,
+    responses(
+        (status = 200, description = "Batch committed", body = BatchResponse),
+        (status = 422, description = "Batch rolled back", body = BatchResponse)
+    )
+)]
+pub async fn batch_update(
+    State(state): State<AppState>,
+    context: RequestContext,
+    ApiJson(request): ApiJson<BatchRequest<BatchUpdate>>,
+) -> BatchResult {
+    run_batch(&state, &context, request, |item| {
+        Ok(BatchOperation::Update {
+            id: item.id,
+            expected_version: item.expected_version,
+            changes: item.changes.validate()?,
+        })
+    })
+    .await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/resources/batch",
+    tag = "batch",
+    params(
+        ("idempotency-key" = Option<String>, Header, description = "Replays the stored response when the same key is reused")
+    ),
+    request_body = BatchRequest<BatchDelete>,
+    responses(
+        (status = 200, description = "Batch committed", body = BatchResponse),
+        (status = 422, description = "Batch rolled back", body = BatchResponse)
+    )
+)]
+pub async fn batch_delete(
+    State(state): State<AppState>,
+    context: RequestContext,
+    ApiJson(request): ApiJson<BatchRequest<BatchDelete>>,
+) -> BatchResult {
+    run_batch(&state, &context, request, |item| {
+        Ok(BatchOperation::Delete {
+            id: item.id,
+            expected_version: item.expected_version,
+        })
+    })
+    .await
+}
+
+async fn run_batch<T>(
+    state: &AppState,
+    context: &RequestContext,
+    request: BatchRequest<T>,
+    prepare: impl Fn(T) -> Result<BatchOperation, ApiError>,
+) -> BatchResult {
+    if request.items.len() > MAX_BATCH_SIZE {
+        return Err(ApiError::BadRequest(format!(
+            "Batch exceeds {MAX_BATCH_SIZE} items"
+        )));
+    }
+    let operations = request.items.into_iter().map(prepare).collect();
+    let response = state
+        .database
+        .run_batch(request.mode, operations, context)
+        .await?;
+    let status = if response.committed {
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+    Ok((status, Json(response)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/resources/export",
+    tag = "transfer",
+    params(
+        ExportQuery
+    ),
+    responses(
+        (status = 200, description = "All active resources", content((Vec<Resource> = "application/json"), (inline(String) = "text/csv"), (inline(String) = "application/x-ndjson")))
+    )
+)]
+pub async fn export_resources(
+    State(state): State<AppState>,
+    ApiQuery(query): ApiQuery<ExportQuery>,
+) -> impl IntoResponse {
+    let format = query.format;
+    let chunks = transfer::encode_stream(format, state.database.stream_resources())
+        .map(|chunk| chunk.map_err(|error| std::io::Error::other(error.to_string())));
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"resources.{}\"", format.extension()),
+            ),
+        ],
+        Body::from_stream(chunks),
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/resources/import",
+    tag = "transfer",
+    params(
+        ImportQuery,
+        ("idempotency-key" = Option<String>, Header, description = "Replays the stored response when the same key is reused")
+    ),
+    request_body(content((Vec<ImportRecord> = "application/json"), (inline(String) = "text/csv"), (inline(String) = "application/x-ndjson"))),
+    responses(
+        (status = 200, description = "Import report", body = ApiResponse<ImportReport>),
+        (status = 413, description = "Payload too large"),
+        (status = 429, description = "Rate limit exceeded", body = ApiErrorResponse)
+    )
+)]
+pub async fn import_resources(
+    State(state): State<AppState>,
+    context: RequestContext,
+    ApiQuery(query): ApiQuery<ImportQuery>,
+    body: String,
+) -> Result<Json<ApiResponse<ImportReport>>, ApiError> {
+    let rows = transfer::parse_import(query.format, &body)?;
+    Ok(Json(ApiResponse {
+        data: state
+            .database
+            .import_resources(rows, query.dry_run, &context)
+            .await?,
     }))
 }
 
-pub async fn delete_resource(
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "Registered webhooks", body = ApiListResponse<Webhook>),
+        (status = 403, description = "Caller is not listed in admin_actors", body = ApiErrorResponse)
+    )
+)]
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+) -> Result<Json<ApiListResponse<Webhook>>, ApiError> {
+    Ok(Json(state.database.list_webhooks().await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    tag = "webhooks",
+    params(
+        ("idempotency-key" = Option<String>, Header, description = "Replays the stored response when the same key is reused")
+    ),
+    request_body = CreateWebhook,
+    responses(
+        (status = 201, description = "Webhook created; the signing secret is only returned here", body = ApiResponse<Webhook>),
+        (status = 422, description = "Validation failed", body = ApiErrorResponse),
+        (status = 403, description = "Caller is not listed in admin_actors", body = ApiErrorResponse)
+    )
+)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    ApiJson(input): ApiJson<CreateWebhook>,
+) -> Result<(StatusCode, Json<ApiResponse<Webhook>>), ApiError> {
+    let input = input.validate()?;
+    webhooks::validate_target(&input.url, state.config.webhook_allow_loopback).await?;
+    let webhook = state.database.create_webhook(input).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse { data: webhook })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = String, Path, description = "Webhook id")
+    ),
+    responses(
+        (status = 200, description = "The webhook", body = ApiResponse<Webhook>),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 403, description = "Caller is not listed in admin_actors", body = ApiErrorResponse)
+    )
+)]
+pub async fn get_webhook(State(state): State<AppState>, Path(id): Path<String>) -> WebhookResult {
+    let webhook = state
+        .database
+        .get_webhook(&id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(ApiResponse { data: webhook }))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = String, Path, description = "Webhook id")
+    ),
+    request_body = UpdateWebhook,
+    responses(
+        (status = 200, description = "Updated webhook; includes the secret when rotated", body = ApiResponse<Webhook>),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 422, description = "Validation failed", body = ApiErrorResponse),
+        (status = 403, description = "Caller is not listed in admin_actors", body = ApiErrorResponse)
+    )
+)]
+pub async fn update_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ApiJson(input): ApiJson<UpdateWebhook>,
+) -> WebhookResult {
+    let input = input.validate()?;
+    if let Some(url) = &input.url {
+        webhooks::validate_target(url, state.config.webhook_allow_loopback).await?;
+    }
+    let webhook = state
+        .database
+        .update_webhook(&id, input)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(ApiResponse { data: webhook }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = String, Path, description = "Webhook id")
+    ),
+    responses(
+        (status = 200, description = "Webhook deleted"),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 403, description = "Caller is not listed in admin_actors", body = ApiErrorResponse)
+    )
+)]
+pub async fn delete_webhook(
     State(state): State<AppState>,
     Path(id): Path<String>,
 ) -> Result<(), ApiError> {
-    if !state.database.delete_resource(&id).await? {
+    if !state.database.delete_webhook(&id).await? {
         return Err(ApiError::NotFound);
     }
     Ok(())
 }
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    params(
+        ("id" = String, Path, description = "Webhook id"),
+        DeliveryQuery
+    ),
+    responses(
+        (status = 200, description = "Deliveries, newest first", body = ApiListResponse<WebhookDelivery>),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 403, description = "Caller is not listed in admin_actors", body = ApiErrorResponse)
+    )
+)]
+pub async fn list_webhook_deliveries(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ApiQuery(query): ApiQuery<DeliveryQuery>,
+) -> DeliveryListResult {
+    state
+        .database
+        .get_webhook(&id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(
+        state.database.list_deliveries(Some(&id), &query).await?,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/dead-letters",
+    tag = "webhooks",
+    params(
+        DeliveryQuery
+    ),
+    responses(
+        (status = 200, description = "Deliveries that exhausted their retries", body = ApiListResponse<WebhookDelivery>),
+        (status = 403, description = "Caller is not listed in admin_actors", body = ApiErrorResponse)
+    )
+)]
+pub async fn list_dead_letters(
+    State(state): State<AppState>,
+    ApiQuery(query): ApiQuery<DeliveryQuery>,
+) -> DeliveryListResult {
+    let query = DeliveryQuery {
+        status: Some(DeliveryStatus::Dead),
+        ..query
+    };
+    Ok(Json(state.database.list_deliveries(None, &query).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/webhooks/deliveries/{id}/retry",
+    tag = "webhooks",
+    params(
+        ("id" = i64, Path, description = "Delivery id"),
+        ("idempotency-key" = Option<String>, Header, description = "Replays the stored response when the same key is reused")
+    ),
+    responses(
+        (status = 200, description = "Delivery queued for another attempt", body = ApiResponse<WebhookDelivery>),
+        (status = 404, description = "Not found", body = ApiErrorResponse),
+        (status = 403, description = "Caller is not listed in admin_actors", body = ApiErrorResponse)
+    )
+)]
+pub async fn retry_delivery(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse<WebhookDelivery>>, ApiError> {
+    let delivery = state
+        .database
+        .retry_delivery(id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(ApiResponse { data: delivery }))
+}
+
+fn item_response(resource: Resource) -> ItemResult {
+    Ok((
+        [(header::ETAG, etag(resource.version))],
+        Json(ApiResponse { data: resource }),
+    ))
+}
+
+fn etag(version: i64) -> String {
+    format!("\"{version}\"")
+}
+
+async fn expected_version(
+    state: &AppState,
+    id: &str,
+    headers: &HeaderMap,
+) -> Result<Option<i64>, ApiError> {
+    let Some(if_match) = headers.get(header::IF_MATCH) else {
+        if state.config.require_if_match {
+            return Err(ApiError::PreconditionRequired);
+        }
+        return Ok(None);
+    };
+    let if_match = if_match
+        .to_str()
+        .map_err(|_| ApiError::BadRequest("Invalid If-Match header".to_string()))?;
+    let current = state
+        .database
+        .get_resource(id)
+        .await?
+        .map(|resource| resource.version);
+    if !if_match_passes(if_match, current) {
+        return Err(ApiError::PreconditionFailed);
+    }
+    Ok(current.filter(|_| if_match.trim() != "*"))
+}
+
+fn if_match_passes(if_match: &str, current_version: Option<i64>) -> bool {
+    let Some(version) = current_version else {
+        return false;
+    };
+    if if_match.trim() == "*" {
+        return true;
+    }
+    let current_tag = etag(version);
+    if_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == current_tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn if_match_any_requires_a_current_resource() {
+        assert!(if_match_passes("*", Some(1)));
+        assert!(if_match_passes(" * ", Some(7)));
+        assert!(!if_match_passes("*", None));
+        assert!(!if_match_passes("\"1\"", None));
+    }
+
+    #[test]
+    fn if_match_compares_listed_tags_strongly() {
+        assert!(if_match_passes("\"3\"", Some(3)));
+        assert!(if_match_passes("\"1\", \"3\"", Some(3)));
+        assert!(!if_match_passes("W/\"3\"", Some(3)));
+        assert!(!if_match_passes("\"1\", W/\"3\"", Some(3)));
+        assert!(!if_match_passes("W/\"2\"", Some(3)));
+        assert!(!if_match_passes("3", Some(3)));
+        assert!(!if_match_passes("w/\"3\"", Some(3)));
+    }
+}
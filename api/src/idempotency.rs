@@ -0,0 +1,346 @@
+use crate::db::{IdempotencyState, SqliteDatabase, StoredResponse};
+use crate::error::ApiError;
+use crate::middleware::Actor;
+use crate::router::IMPORT_BODY_LIMIT;
+use axum::body::{Body, Bytes, to_bytes};
+use axum::extract::Request;
+use axum::http::uri::PathAndQuery;
+use axum::http::{HeaderName, HeaderValue, Method, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use chrono::TimeDelta;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower::{Layer, Service};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const IDEMPOTENT_REPLAYED_HEADER: HeaderName = HeaderName::from_static("idempotent-replayed");
+const KEY_MAX_LENGTH: usize = 255;
+const IN_PROGRESS_LEASE: TimeDelta = TimeDelta::seconds(60);
+const LEASE_RENEWAL: Duration = Duration::from_secs(20);
+
+#[derive(Clone)]
+pub struct IdempotencyLayer {
+    database: SqliteDatabase,
+    ttl: TimeDelta,
+}
+
+impl IdempotencyLayer {
+    pub fn new(database: SqliteDatabase, ttl: TimeDelta) -> Self {
+        Self { database, ttl }
+    }
+}
+
+impl<S> Layer<S> for IdempotencyLayer {
+    type Service = IdempotencyMiddleware<S>;
+    fn layer(&self, inner: S) -> Self::Service {
+        IdempotencyMiddleware {
+            inner,
+            database: self.database.clone(),
+            ttl: self.ttl,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct IdempotencyMiddleware<S> {
+    inner: S,
+    database: SqliteDatabase,
+    ttl: TimeDelta,
+}
+
+impl<S> Service<Request<Body>> for IdempotencyMiddleware<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: Send,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, context: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(context)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let key = applies(&request)
+            .then(|| request.headers().get(IDEMPOTENCY_KEY_HEADER).cloned())
+            .flatten();
+        let Some(key) = key else {
+            return Box::pin(inner.call(request));
+        };
+        let database = self.database.clone();
+        let ttl = self.ttl;
+        Box::pin(async move {
+            let Some(key) = key
+                .to_str()
+                .ok()
+                .map(str::trim)
+                .filter(|key| !key.is_empty() && key.len() <= KEY_MAX_LENGTH)
+                .map(str::to_string)
+            else {
+                return Ok(
+                    ApiError::BadRequest("Invalid Idempotency-Key header".to_string())
+                        .into_response(),
+                );
+            };
+            let actor = request
+                .extensions()
+                .get::<Actor>()
+                .cloned()
+                .unwrap_or_else(Actor::anonymous)
+                .0;
+            let (parts, body) = request.into_parts();
+            let Ok(body) = to_bytes(body, IMPORT_BODY_LIMIT).await else {
+                return Ok(ApiError::PayloadTooLarge.into_response());
+            };
+            let path = parts.uri.path_and_query().map_or("/", PathAndQuery::as_str);
+            let request_hash = request_hash(&parts.method, path, &body);
+            let started = match database
+                .begin_idempotent(&actor, &key, &request_hash, ttl, IN_PROGRESS_LEASE)
+                .await
+            {
+                Ok(IdempotencyState::Started(started)) => started,
+                Ok(IdempotencyState::Replay(stored)) => return Ok(replay(stored)),
+                Ok(IdempotencyState::Mismatch) => {
+                    return Ok(ApiError::Conflict(
+                        "Idempotency-Key was already used with a different request".to_string(),
+                    )
+                    .into_response());
+                }
+                Ok(IdempotencyState::InProgress) => {
+                    return Ok(ApiError::Conflict(
+                        "A request with this Idempotency-Key is still in progress".to_string(),
+                    )
+                    .into_response());
+                }
+                Err(error) => return Ok(error.into_response()),
+            };
+
+            let handler = inner.call(Request::from_parts(parts, Body::from(body)));
+            let response =
+                hold_lease(&database, &actor, &key, &started, LEASE_RENEWAL, handler).await?;
+            if response.status().is_server_error() {
+                abandon(&database, &actor, &key, &started).await;
+                return Ok(response);
+            }
+            let (parts, body) = response.into_parts();
+            let body = match to_bytes(body, usize::MAX).await {
+                Ok(body) => body,
+                Err(error) => {
+                    abandon(&database, &actor, &key, &started).await;
+                    return Ok(ApiError::Database(error.to_string()).into_response());
+                }
+            };
+            let header = |name: HeaderName| {
+                parts
+                    .headers
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string)
+            };
+            let stored = StoredResponse {
+                status: parts.status.as_u16(),
+                content_type: header(header::CONTENT_TYPE),
+                etag: header(header::ETAG),
+                body: body.to_vec(),
+            };
+            if let Err(error) = database
+                .complete_idempotent(&actor, &key, &started, &stored)
+                .await
+            {
+                tracing::warn!("Failed to store idempotent response: {error}");
+            }
+            Ok(Response::from_parts(parts, Body::from(body)))
+        })
+    }
+}
+
+fn applies(request: &Request<Body>) -> bool {
+    request.method() == Method::POST || request.uri().path().ends_with("/resources/batch")
+}
+
+fn request_hash(method: &Method, path: &str, body: &Bytes) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str());
+    hasher.update(b" ");
+    hasher.update(path);
+    hasher.update(b"\n");
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let mut response = Response::new(Body::from(stored.body));
+    *response.status_mut() = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let headers = response.headers_mut();
+    for (name, value) in [
+        (header::CONTENT_TYPE, stored.content_type),
+        (header::ETAG, stored.etag),
+    ] {
+        if let Some(value) = value.and_then(|value| HeaderValue::from_str(&value).ok()) {
+            headers.insert(name, value);
+        }
+    }
+    headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+async fn hold_lease<F: Future>(
+    database: &SqliteDatabase,
+    actor: &str,
+    key: &str,
+    started: &str,
+    renewal: Duration,
+    handler: F,
+) -> F::Output {
+    let mut handler = std::pin::pin!(handler);
+    loop {
+        tokio::select! {
+            output = &mut handler => return output,
+            _ = tokio::time::sleep(renewal) => {
+                if let Err(error) = database
+                    .renew_idempotent(actor, key, started, IN_PROGRESS_LEASE)
+                    .await
+                {
+                    tracing::warn!("Failed to renew Idempotency-Key lease: {error}");
+                }
+            }
+        }
+    }
+}
+
+async fn abandon(database: &SqliteDatabase, actor: &str, key: &str, started: &str) {
+    if let Err(error) = database.abandon_idempotent(actor, key, started).await {
+        tracing::warn!("Failed to release Idempotency-Key: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use axum::Router;
+    use axum::routing::post;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    async fn database() -> SqliteDatabase {
+        let database = SqliteDatabase::new(&DatabaseConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+            ..DatabaseConfig::default()
+        })
+        .await
+        .unwrap();
+        database.migrate().await.unwrap();
+        database
+    }
+
+    async fn send(app: &Router, uri: &str) -> Response {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri(uri)
+                    .header(IDEMPOTENCY_KEY_HEADER, "import-1")
+                    .body(Body::from("[]"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn query_string_is_part_of_the_request_fingerprint() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let app = Router::new()
+            .route(
+                "/import",
+                post(move || async move { counter.fetch_add(1, Ordering::SeqCst).to_string() }),
+            )
+            .layer(IdempotencyLayer::new(database().await, TimeDelta::hours(1)));
+
+        assert_eq!(
+            send(&app, "/import?dry_run=true").await.status(),
+            StatusCode::OK
+        );
+        let replayed = send(&app, "/import?dry_run=true").await;
+        assert_eq!(replayed.status(), StatusCode::OK);
+        assert!(replayed.headers().contains_key(IDEMPOTENT_REPLAYED_HEADER));
+        assert_eq!(send(&app, "/import").await.status(), StatusCode::CONFLICT);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn stale_in_progress_keys_are_reclaimed_after_the_lease() {
+        let database = database().await;
+        let ttl = TimeDelta::hours(1);
+        let begin = |lease| database.begin_idempotent("anonymous", "key", "hash", ttl, lease);
+
+        let Ok(IdempotencyState::Started(first)) = begin(TimeDelta::zero()).await else {
+            panic!("first request should start");
+        };
+        let Ok(IdempotencyState::Started(second)) = begin(IN_PROGRESS_LEASE).await else {
+            panic!("an expired lease should be reclaimed");
+        };
+        assert_ne!(first, second);
+        assert!(matches!(
+            begin(IN_PROGRESS_LEASE).await,
+            Ok(IdempotencyState::InProgress)
+        ));
+
+        let stored = |status| StoredResponse {
+            status,
+            content_type: None,
+            etag: None,
+            body: Vec::new(),
+        };
+        database
+            .complete_idempotent("anonymous", "key", &first, &stored(500))
+            .await
+            .unwrap();
+        database
+            .complete_idempotent("anonymous", "key", &second, &stored(201))
+            .await
+            .unwrap();
+        let Ok(IdempotencyState::Replay(replayed)) = begin(IN_PROGRESS_LEASE).await else {
+            panic!("completed request should replay");
+        };
+        assert_eq!(replayed.status, 201);
+    }
+
+    #[tokio::test]
+    async fn running_handlers_keep_renewing_their_lease() {
+        let database = database().await;
+        let ttl = TimeDelta::hours(1);
+        let Ok(IdempotencyState::Started(started)) = database
+            .begin_idempotent("anonymous", "key", "hash", ttl, TimeDelta::zero())
+            .await
+        else {
+            panic!("first request should start");
+        };
+        let handler = tokio::time::sleep(Duration::from_millis(50));
+        hold_lease(
+            &database,
+            "anonymous",
+            "key",
+            &started,
+            Duration::from_millis(10),
+            handler,
+        )
+        .await;
+        assert!(matches!(
+            database
+                .begin_idempotent("anonymous", "key", "hash", ttl, IN_PROGRESS_LEASE)
+                .await,
+            Ok(IdempotencyState::InProgress)
+        ));
+    }
+}
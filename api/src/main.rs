@@ -1,31 +1,193 @@
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use clap::Parser;
+use futures_util::FutureExt;
+use std::future::IntoFuture;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 
+mod cli;
 mod config;
+mod context;
+mod cors;
 mod db;
+#[cfg(feature = "dev-tools")]
+mod dev_tools;
 mod error;
+mod events;
+mod extract;
 mod handlers;
+mod idempotency;
+mod metrics;
 mod middleware;
+mod openapi;
+mod purge;
+mod rate_limit;
+mod realtime;
+mod request_id;
 mod router;
+mod shutdown;
 mod state;
+mod telemetry;
+mod tls;
+mod transfer;
+mod webhooks;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
-        .with(tracing_subscriber::EnvFilter::from_default_env())
-        .init();
+    let cli = cli::Cli::parse();
     dotenvy::dotenv().ok();
-    let config = config::Config::from_env();
+    let config = match config::Config::load(
+        cli.config.as_deref(),
+        config::Overrides {
+            bind_address: cli.bind,
+            database_url: cli.database_url,
+            log_format: cli.log_format,
+        },
+    ) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(2);
+        }
+    };
+    let telemetry = telemetry::init(&config)?;
+    let result = run(cli.command, config).await;
+    telemetry.shutdown();
+    result
+}
+
+async fn run(
+    command: Option<cli::Command>,
+    config: config::Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let command = match command {
+        #[cfg(feature = "dev-tools")]
+        Some(cli::Command::ReceiveWebhooks {
+            listen,
+            secret,
+            status,
+        }) => return dev_tools::receive_webhooks(listen, secret, status).await,
+        #[cfg(feature = "dev-tools")]
+        Some(cli::Command::ReceiveTraces { listen }) => {
+            return dev_tools::receive_traces(listen).await;
+        }
+        command => command,
+    };
     std::fs::create_dir_all("./data").ok();
-    let database = db::SqliteDatabase::new(&config.database_url).await?;
+    let database = db::SqliteDatabase::new(&config.database).await?;
     database.migrate().await?;
+    match command {
+        Some(cli::Command::Export { format, output }) => {
+            return cli::export(&database, format, output).await;
+        }
+        Some(cli::Command::Import {
+            format,
+            dry_run,
+            input,
+        }) => return cli::import(&database, format, dry_run, input).await,
+        _ => {}
+    }
+    let drain_timeout = Duration::from_secs(config.drain_timeout_secs);
+    let shutdown = shutdown::listen();
     let addr: std::net::SocketAddr = config.bind_address.parse()?;
-    tracing::info!("Starting server on {addr}");
+    let tls = tls::Tls::load(&config.tls).await?;
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(
-        listener,
-        router::create_router(state::AppState { database, config }),
+    let https_redirect = match (&tls, &config.tls.redirect_http_bind) {
+        (Some(_), Some(bind)) => Some(
+            tls::spawn_https_redirect(
+                bind.parse()?,
+                config.tls.public_https_port.unwrap_or(addr.port()),
+                shutdown.clone(),
+            )
+            .await?,
+        ),
+        _ => None,
+    };
+    let tls_reload_cancel = CancellationToken::new();
+    let tls_reload = tls.as_ref().map(|tls| {
+        tls.spawn_reload(
+            Duration::from_secs(config.tls.reload_interval_secs),
+            tls_reload_cancel.clone(),
+        )
+    });
+    let purge_cancel = CancellationToken::new();
+    let purge = purge::spawn_trash_purge(
+        database.clone(),
+        chrono::TimeDelta::days(config.trash_retention_days),
+        Duration::from_secs(config.trash_purge_interval_secs),
+        purge_cancel.clone(),
+    );
+    let webhooks_cancel = CancellationToken::new();
+    let webhooks = webhooks::spawn_webhook_dispatcher(
+        database.clone(),
+        webhooks::WebhookSettings {
+            max_attempts: config.webhook_max_attempts,
+            retry_base: Duration::from_secs(config.webhook_retry_base_secs),
+            timeout: Duration::from_secs(config.webhook_timeout_secs),
+            allow_loopback: config.webhook_allow_loopback,
+        },
+        webhooks_cancel.clone(),
+    );
+    let app = router::create_router(state::AppState {
+        database: database.clone(),
+        config,
+        metrics: metrics::Metrics::default(),
+        shutdown: shutdown.clone(),
+    })
+    .into_make_service_with_connect_info::<std::net::SocketAddr>();
+    let server = match &tls {
+        Some(tls) => {
+            tracing::info!("Starting server on https://{addr}");
+            tls.serve(listener, app, shutdown.clone()).boxed()
+        }
+        None => {
+            tracing::info!("Starting server on {addr}");
+            axum::serve(listener, app)
+                .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+                .into_future()
+                .boxed()
+        }
+    };
+    let served = tokio::select! {
+        result = server => result,
+        _ = async {
+            shutdown.cancelled().await;
+            tokio::time::sleep(drain_timeout).await;
+        } => {
+            tracing::warn!(
+                "Connections still open after {}s drain timeout, closing them",
+                drain_timeout.as_secs()
+            );
+            Ok(())
+        }
+    };
+    shutdown.cancel();
+    if let Some(https_redirect) = https_redirect {
+        shutdown::stop_task(
+            "HTTPS redirect",
+            shutdown.clone(),
+            https_redirect,
+            drain_timeout,
+        )
+        .await;
+    }
+    if let Some(tls_reload) = tls_reload {
+        shutdown::stop_task(
+            "TLS certificate reload",
+            tls_reload_cancel,
+            tls_reload,
+            drain_timeout,
+        )
+        .await;
+    }
+    shutdown::stop_task(
+        "webhook dispatcher",
+        webhooks_cancel,
+        webhooks,
+        drain_timeout,
     )
-    .await?;
-    Ok(())
+    .await;
+    shutdown::stop_task("trash purge", purge_cancel, purge, drain_timeout).await;
+    database.close().await;
+    tracing::info!("Shutdown complete");
+    Ok(served?)
 }
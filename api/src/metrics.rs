@@ -0,0 +1,229 @@
+use crate::db::{PoolStats, ResourceCounts};
+use crate::router::matched_route;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::response::Response;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tower::{Layer, Service};
+
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Default)]
+struct Series {
+    count: u64,
+    sum: f64,
+    buckets: [u64; LATENCY_BUCKETS.len()],
+}
+
+type SeriesKey = (String, String, u16);
+
+#[derive(Clone, Default)]
+pub struct Metrics {
+    requests: Arc<Mutex<BTreeMap<SeriesKey, Series>>>,
+    in_flight: Arc<AtomicI64>,
+}
+
+impl Metrics {
+    pub fn layer(&self) -> MetricsLayer {
+        MetricsLayer {
+            metrics: self.clone(),
+        }
+    }
+
+    fn observe(&self, key: SeriesKey, seconds: f64) {
+        let mut requests = self
+            .requests
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let series = requests.entry(key).or_default();
+        series.count += 1;
+        series.sum += seconds;
+        for (bucket, bound) in series.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+    }
+
+    pub fn render(&self, pool: PoolStats, resources: Option<ResourceCounts>) -> String {
+        let mut output = String::new();
+        let requests = self
+            .requests
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+
+        output.push_str("# HELP http_requests_total Total HTTP requests by route and status.\n");
+        output.push_str("# TYPE http_requests_total counter\n");
+        for ((method, route, status), series) in requests.iter() {
+            let labels = labels(method, route, *status);
+            writeln!(output, "http_requests_total{{{labels}}} {}", series.count).ok();
+        }
+
+        output.push_str(
+            "# HELP http_request_duration_seconds HTTP request latency by route and status.\n",
+        );
+        output.push_str("# TYPE http_request_duration_seconds histogram\n");
+        for ((method, route, status), series) in requests.iter() {
+            let labels = labels(method, route, *status);
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(series.buckets) {
+                writeln!(
+                    output,
+                    "http_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {count}"
+                )
+                .ok();
+            }
+            writeln!(
+                output,
+                "http_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                series.count
+            )
+            .ok();
+            writeln!(
+                output,
+                "http_request_duration_seconds_sum{{{labels}}} {}",
+                series.sum
+            )
+            .ok();
+            writeln!(
+                output,
+                "http_request_duration_seconds_count{{{labels}}} {}",
+                series.count
+            )
+            .ok();
+        }
+        drop(requests);
+
+        output.push_str("# HELP http_requests_in_flight HTTP requests currently being served.\n");
+        output.push_str("# TYPE http_requests_in_flight gauge\n");
+        writeln!(
+            output,
+            "http_requests_in_flight {}",
+            self.in_flight.load(Ordering::Relaxed)
+        )
+        .ok();
+
+        output.push_str("# HELP db_pool_connections Database pool connections by state.\n");
+        output.push_str("# TYPE db_pool_connections gauge\n");
+        writeln!(
+            output,
+            "db_pool_connections{{state=\"active\"}} {}",
+            pool.size.saturating_sub(pool.idle)
+        )
+        .ok();
+        writeln!(
+            output,
+            "db_pool_connections{{state=\"idle\"}} {}",
+            pool.idle
+        )
+        .ok();
+        output.push_str("# HELP db_pool_max_connections Database pool connection limit.\n");
+        output.push_str("# TYPE db_pool_max_connections gauge\n");
+        writeln!(output, "db_pool_max_connections {}", pool.max).ok();
+
+        if let Some(resources) = resources {
+            output.push_str("# HELP resources Stored resources by state.\n");
+            output.push_str("# TYPE resources gauge\n");
+            writeln!(output, "resources{{state=\"active\"}} {}", resources.active).ok();
+            writeln!(
+                output,
+                "resources{{state=\"trashed\"}} {}",
+                resources.trashed
+            )
+            .ok();
+        }
+        output
+    }
+}
+
+fn labels(method: &str, route: &str, status: u16) -> String {
+    format!(
+        "method=\"{method}\",route=\"{}\",status=\"{status}\"",
+        escape(route)
+    )
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+struct InFlight<'a>(&'a AtomicI64);
+
+impl<'a> InFlight<'a> {
+    fn new(gauge: &'a AtomicI64) -> Self {
+        gauge.fetch_add(1, Ordering::Relaxed);
+        Self(gauge)
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone)]
+pub struct MetricsLayer {
+    metrics: Metrics,
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsMiddleware<S>;
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsMiddleware {
+            inner,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MetricsMiddleware<S> {
+    inner: S,
+    metrics: Metrics,
+}
+
+impl<S> Service<Request<Body>> for MetricsMiddleware<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, context: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(context)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let metrics = self.metrics.clone();
+        let method = request.method().to_string();
+        let route = matched_route(&request).to_string();
+        Box::pin(async move {
+            let started = Instant::now();
+            let in_flight = InFlight::new(&metrics.in_flight);
+            let result = inner.call(request).await;
+            drop(in_flight);
+            if let Ok(response) = &result {
+                metrics.observe(
+                    (method, route, response.status().as_u16()),
+                    started.elapsed().as_secs_f64(),
+                );
+            }
+            result
+        })
+    }
+}
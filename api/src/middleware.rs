@@ -1,20 +1,45 @@
+use crate::error::ApiError;
 use axum::body::Body;
 use axum::extract::Request;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use tower::{Layer, Service};
 
+const API_KEY_HEADER: &str = "x-api-key";
+
+type KeyDigests = Arc<HashMap<[u8; 32], String>>;
+
+#[derive(Clone, Debug)]
+pub struct Actor(pub String);
+
+impl Actor {
+    pub fn anonymous() -> Self {
+        Self("anonymous".to_string())
+    }
+}
+
 #[derive(Clone)]
 pub struct AuthLayer {
     enabled: bool,
+    keys: KeyDigests,
 }
 
 impl AuthLayer {
-    pub fn new(enabled: bool) -> Self {
-        Self { enabled }
+    pub fn new(enabled: bool, api_keys: &BTreeMap<String, String>) -> Self {
+        let keys = api_keys
+            .iter()
+            .map(|(actor, key)| (Sha256::digest(key).into(), actor.clone()))
+            .collect();
+        Self {
+            enabled,
+            keys: Arc::new(keys),
+        }
     }
 }
 
@@ -24,6 +49,7 @@ impl<S> Layer<S> for AuthLayer {
         AuthMiddleware {
             inner,
             enabled: self.enabled,
+            keys: self.keys.clone(),
         }
     }
 }
@@ -32,6 +58,7 @@ impl<S> Layer<S> for AuthLayer {
 pub struct AuthMiddleware<S> {
     inner: S,
     enabled: bool,
+    keys: KeyDigests,
 }
 
 impl<S> Service<Request<Body>> for AuthMiddleware<S>
@@ -47,26 +74,89 @@ where
         self.inner.poll_ready(context)
     }
 
-    fn call(&mut self, request: Request<Body>) -> Self::Future {
+    fn call(&mut self, mut request: Request<Body>) -> Self::Future {
         let enabled = self.enabled;
+        let keys = self.keys.clone();
         let mut inner = self.inner.clone();
         Box::pin(async move {
             if !enabled {
                 return inner.call(request).await;
             }
-            let auth_header = request
-                .headers()
+            let headers = request.headers();
+            let presented = headers
                 .get(http::header::AUTHORIZATION)
-                .and_then(|h| h.to_str().ok());
-            if validate_auth(auth_header) {
-                inner.call(request).await
-            } else {
-                Ok(StatusCode::UNAUTHORIZED.into_response())
+                .and_then(|h| h.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .or_else(|| headers.get(API_KEY_HEADER).and_then(|h| h.to_str().ok()));
+            match validate_auth(presented, &keys) {
+                Some(actor) => {
+                    request.extensions_mut().insert(actor);
+                    inner.call(request).await
+                }
+                None => Ok(StatusCode::UNAUTHORIZED.into_response()),
             }
         })
     }
 }
 
-fn validate_auth(_header: Option<&str>) -> bool {
-    true
+fn validate_auth(key: Option<&str>, keys: &HashMap<[u8; 32], String>) -> Option<Actor> {
+    let digest: [u8; 32] = Sha256::digest(key?.trim()).into();
+    keys.get(&digest).cloned().map(Actor)
+}
+
+#[derive(Clone)]
+pub struct AdminLayer {
+    admins: Arc<BTreeSet<String>>,
+}
+
+impl AdminLayer {
+    pub fn new(admin_actors: &[String]) -> Self {
+        Self {
+            admins: Arc::new(admin_actors.iter().cloned().collect()),
+        }
+    }
+}
+
+impl<S> Layer<S> for AdminLayer {
+    type Service = AdminMiddleware<S>;
+    fn layer(&self, inner: S) -> Self::Service {
+        AdminMiddleware {
+            inner,
+            admins: self.admins.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AdminMiddleware<S> {
+    inner: S,
+    admins: Arc<BTreeSet<String>>,
+}
+
+impl<S> Service<Request<Body>> for AdminMiddleware<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, context: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(context)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let actor = request
+            .extensions()
+            .get::<Actor>()
+            .cloned()
+            .unwrap_or_else(Actor::anonymous);
+        if !self.admins.contains(&actor.0) {
+            return Box::pin(async {
+                Ok(ApiError::Forbidden("Admin access required".to_string()).into_response())
+            });
+        }
+        Box::pin(self.inner.call(request))
+    }
 }
@@ -0,0 +1,229 @@
+use crate::handlers;
+use api_types::responses::ApiErrorResponse;
+use utoipa::openapi::{OpenApi as Spec, RefOr, ResponseBuilder};
+use utoipa::{Modify, OpenApi, PartialSchema, ToSchema};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "native-leptos API",
+        description = "Resource management API used by the native-leptos desktop app"
+    ),
+    servers((url = "/api/v1")),
+    paths(
+        handlers::list_resources,
+        handlers::create_resource,
+        handlers::batch_create,
+        handlers::batch_update,
+        handlers::batch_delete,
+        handlers::search_resources,
+        handlers::resource_events,
+        handlers::export_resources,
+        handlers::import_resources,
+        handlers::list_trash,
+        handlers::purge_resource,
+        handlers::restore_resource,
+        handlers::resource_history,
+        handlers::list_revisions,
+        handlers::diff_revisions,
+        handlers::restore_revision,
+        handlers::list_audit_entries,
+        handlers::websocket,
+        handlers::list_webhooks,
+        handlers::create_webhook,
+        handlers::list_dead_letters,
+        handlers::retry_delivery,
+        handlers::get_webhook,
+        handlers::update_webhook,
+        handlers::delete_webhook,
+        handlers::list_webhook_deliveries,
+        handlers::get_resource,
+        handlers::replace_resource,
+        handlers::patch_resource,
+        handlers::delete_resource,
+    ),
+    modifiers(&ErrorResponses),
+    tags(
+        (name = "resources", description = "Create, read, update and delete resources"),
+        (name = "trash", description = "Soft-deleted resources"),
+        (name = "revisions", description = "Resource revision history"),
+        (name = "audit", description = "Audit log"),
+        (name = "batch", description = "Bulk operations"),
+        (name = "transfer", description = "Import and export"),
+        (name = "events", description = "Realtime change notifications"),
+        (name = "webhooks", description = "Webhook subscriptions and deliveries"),
+    )
+)]
+pub struct ApiDoc;
+
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut Spec) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components
+            .schemas
+            .entry(ApiErrorResponse::name().into_owned())
+            .or_insert_with(ApiErrorResponse::schema);
+        let error = ResponseBuilder::new()
+            .description("Error")
+            .content(
+                "application/json",
+                utoipa::openapi::ContentBuilder::new()
+                    .schema(Some(RefOr::Ref(utoipa::openapi::Ref::from_schema_name(
+                        ApiErrorResponse::name(),
+                    ))))
+                    .build(),
+            )
+            .build();
+        for item in openapi.paths.paths.values_mut() {
+            for operation in [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.patch,
+                &mut item.delete,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                operation
+                    .responses
+                    .responses
+                    .entry("default".to_string())
+                    .or_insert_with(|| error.clone().into());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, DatabaseConfig};
+    use crate::db::SqliteDatabase;
+    use crate::metrics::Metrics;
+    use crate::rate_limit::RateLimiter;
+    use crate::router::{api_routes, create_router};
+    use crate::state::AppState;
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode, header};
+    use std::collections::{BTreeMap, BTreeSet};
+    use tokio_util::sync::CancellationToken;
+    use tower::ServiceExt;
+
+    type Routes = BTreeMap<String, BTreeSet<String>>;
+
+    fn spec_routes() -> Routes {
+        ApiDoc::openapi()
+            .paths
+            .paths
+            .into_iter()
+            .map(|(path, item)| {
+                let methods = [
+                    ("GET", item.get.is_some()),
+                    ("PUT", item.put.is_some()),
+                    ("POST", item.post.is_some()),
+                    ("PATCH", item.patch.is_some()),
+                    ("DELETE", item.delete.is_some()),
+                ]
+                .into_iter()
+                .filter(|(_, present)| *present)
+                .map(|(method, _)| method.to_string())
+                .collect();
+                (path, methods)
+            })
+            .collect()
+    }
+
+    async fn routed_methods(paths: Vec<String>) -> Routes {
+        let database = SqliteDatabase::new(&DatabaseConfig {
+            url: "sqlite::memory:".to_string(),
+            ..DatabaseConfig::default()
+        })
+        .await
+        .unwrap();
+        database.migrate().await.unwrap();
+        let config = Config {
+            rate_limit_enabled: false,
+            ..Config::default()
+        };
+        let router = create_router(AppState {
+            database,
+            config,
+            metrics: Metrics::default(),
+            shutdown: CancellationToken::new(),
+        });
+        let mut routes = Routes::new();
+        for path in paths {
+            let uri = path
+                .split('/')
+                .map(|segment| {
+                    if segment.starts_with('{') {
+                        "1"
+                    } else {
+                        segment
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            let response = router
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(Method::TRACE)
+                        .uri(format!("/api/v1{uri}"))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(
+                response.status(),
+                StatusCode::METHOD_NOT_ALLOWED,
+                "{path} is not routed"
+            );
+            let methods = response.headers()[header::ALLOW]
+                .to_str()
+                .unwrap()
+                .split(',')
+                .map(str::trim)
+                .filter(|method| *method != "HEAD")
+                .map(str::to_string)
+                .collect();
+            routes.insert(path, methods);
+        }
+        routes
+    }
+
+    #[tokio::test]
+    async fn spec_matches_routes() {
+        let spec = spec_routes();
+        let documented: BTreeSet<&str> = spec.keys().map(String::as_str).collect();
+        let paths: BTreeSet<&str> = api_routes(&Config::default(), &RateLimiter::new(false))
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(
+            paths, documented,
+            "api_router paths and OpenAPI paths differ"
+        );
+        let routed = routed_methods(paths.into_iter().map(str::to_string).collect()).await;
+        assert_eq!(
+            spec, routed,
+            "OpenAPI methods and api_router methods differ"
+        );
+    }
+
+    #[test]
+    fn spec_is_openapi_3_1() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        assert_eq!(spec["openapi"], "3.1.0");
+        assert!(spec["components"]["schemas"]["ApiErrorResponse"].is_object());
+        for item in spec["paths"].as_object().unwrap().values() {
+            for operation in item.as_object().unwrap().values() {
+                assert!(operation["responses"]["default"].is_object());
+            }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use crate::db::SqliteDatabase;
+use chrono::{TimeDelta, Utc};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+pub fn spawn_trash_purge(
+    database: SqliteDatabase,
+    retention: TimeDelta,
+    interval: Duration,
+    cancel: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = cancel.cancelled() => return,
+            }
+            match database.purge_deleted_before(Utc::now() - retention).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("Purged {count} resources from trash"),
+                Err(error) => tracing::error!("Trash purge failed: {error}"),
+            }
+        }
+    })
+}
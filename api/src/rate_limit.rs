@@ -0,0 +1,388 @@
+use crate::error::ApiError;
+use crate::middleware::Actor;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, FromRequestParts, Request};
+use axum::http::request::Parts;
+use axum::http::{Extensions, HeaderMap, HeaderName, HeaderValue, header};
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tower::{Layer, Service};
+
+const RATE_LIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+const RATE_LIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+const RATE_LIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+
+#[derive(Clone, Copy)]
+pub struct RateLimitPolicy {
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+impl RateLimitPolicy {
+    fn refill_per_second(&self) -> f64 {
+        f64::from(self.per_minute.max(1)) / 60.0
+    }
+
+    fn capacity(&self) -> f64 {
+        f64::from(self.burst.max(1))
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+    policy: RateLimitPolicy,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        self.tokens = (self.tokens
+            + now.duration_since(self.updated_at).as_secs_f64() * self.policy.refill_per_second())
+        .min(self.policy.capacity());
+        self.updated_at = now;
+    }
+}
+
+struct Decision {
+    allowed: bool,
+    limit: u32,
+    remaining: u32,
+    reset_secs: u64,
+    retry_after_secs: u64,
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    enabled: bool,
+    buckets: Arc<Mutex<HashMap<(&'static str, String), Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            buckets: Arc::default(),
+        }
+    }
+
+    pub fn layer(&self, scope: &'static str, policy: RateLimitPolicy) -> RateLimitLayer {
+        RateLimitLayer {
+            limiter: self.clone(),
+            scope,
+            policy,
+        }
+    }
+
+    pub fn messages(&self, global: RateLimitPolicy, search: RateLimitPolicy) -> MessageLimiter {
+        MessageLimiter {
+            limiter: self.clone(),
+            global,
+            search,
+        }
+    }
+
+    fn buckets(&self) -> MutexGuard<'_, HashMap<(&'static str, String), Bucket>> {
+        self.buckets
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn check(&self, scope: &'static str, client: String, policy: RateLimitPolicy) -> Decision {
+        let now = Instant::now();
+        let rate = policy.refill_per_second();
+        let capacity = policy.capacity();
+        let mut buckets = self.buckets();
+        if buckets.len() >= MAX_TRACKED_BUCKETS {
+            buckets.retain(|_, bucket| {
+                bucket.refill(now);
+                bucket.tokens < bucket.policy.capacity()
+            });
+        }
+        let bucket = buckets.entry((scope, client)).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+            policy,
+        });
+        bucket.refill(now);
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        Decision {
+            allowed,
+            limit: policy.burst.max(1),
+            remaining: bucket.tokens.floor() as u32,
+            reset_secs: ((capacity - bucket.tokens) / rate).ceil() as u64,
+            retry_after_secs: ((1.0 - bucket.tokens) / rate).ceil().max(1.0) as u64,
+        }
+    }
+
+    fn refund(&self, scope: &'static str, client: String) {
+        if let Some(bucket) = self.buckets().get_mut(&(scope, client)) {
+            bucket.tokens = (bucket.tokens + 1.0).min(bucket.policy.capacity());
+        }
+    }
+}
+
+/// Marks a response denied by a scoped limiter so outer limiters give their token back.
+#[derive(Clone, Copy)]
+struct Denied;
+
+#[derive(Clone)]
+pub struct MessageLimiter {
+    limiter: RateLimiter,
+    global: RateLimitPolicy,
+    search: RateLimitPolicy,
+}
+
+impl MessageLimiter {
+    pub fn check(&self, client: &str, search: bool) -> Result<(), ApiError> {
+        let limiter = &self.limiter;
+        if !limiter.enabled {
+            return Ok(());
+        }
+        if search
+            && !limiter
+                .check("search", client.to_string(), self.search)
+                .allowed
+        {
+            return Err(ApiError::RateLimited);
+        }
+        if !limiter
+            .check("global", client.to_string(), self.global)
+            .allowed
+        {
+            if search {
+                limiter.refund("search", client.to_string());
+            }
+            return Err(ApiError::RateLimited);
+        }
+        Ok(())
+    }
+}
+
+pub struct ClientKey(pub String);
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientKey {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Infallible> {
+        Ok(Self(client_key(&parts.extensions)))
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: RateLimiter,
+    scope: &'static str,
+    policy: RateLimitPolicy,
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitMiddleware<S>;
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitMiddleware {
+            inner,
+            limiter: self.limiter.clone(),
+            scope: self.scope,
+            policy: self.policy,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitMiddleware<S> {
+    inner: S,
+    limiter: RateLimiter,
+    scope: &'static str,
+    policy: RateLimitPolicy,
+}
+
+impl<S> Service<Request<Body>> for RateLimitMiddleware<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, context: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(context)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        if !self.limiter.enabled {
+            return Box::pin(inner.call(request));
+        }
+        let limiter = self.limiter.clone();
+        let scope = self.scope;
+        let client = client_key(request.extensions());
+        let decision = limiter.check(scope, client.clone(), self.policy);
+        Box::pin(async move {
+            let mut response = if decision.allowed {
+                let response = inner.call(request).await?;
+                if response.extensions().get::<Denied>().is_some() {
+                    limiter.refund(scope, client);
+                }
+                response
+            } else {
+                let mut response = ApiError::RateLimited.into_response();
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, decision.retry_after_secs.into());
+                response.extensions_mut().insert(Denied);
+                response
+            };
+            let headers = response.headers_mut();
+            if !headers.contains_key(RATE_LIMIT_LIMIT) {
+                insert_headers(headers, &decision);
+            }
+            Ok(response)
+        })
+    }
+}
+
+fn insert_headers(headers: &mut HeaderMap, decision: &Decision) {
+    headers.insert(RATE_LIMIT_LIMIT, HeaderValue::from(decision.limit));
+    headers.insert(RATE_LIMIT_REMAINING, HeaderValue::from(decision.remaining));
+    headers.insert(RATE_LIMIT_RESET, HeaderValue::from(decision.reset_secs));
+}
+
+fn client_key(extensions: &Extensions) -> String {
+    if let Some(actor) = extensions.get::<Actor>() {
+        return format!("user:{}", actor.0);
+    }
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| format!("ip:{}", info.0.ip()))
+        .unwrap_or_else(|| "ip:unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    const SEARCH: RateLimitPolicy = RateLimitPolicy {
+        per_minute: 120,
+        burst: 100,
+    };
+    const ONE: RateLimitPolicy = RateLimitPolicy {
+        per_minute: 1,
+        burst: 1,
+    };
+    const IMPORT: RateLimitPolicy = RateLimitPolicy {
+        per_minute: 6,
+        burst: 6,
+    };
+
+    fn request(actor: Option<&str>) -> Request<Body> {
+        let mut request = Request::builder()
+            .uri("/search")
+            .header("x-api-key", "not-a-configured-key")
+            .body(Body::empty())
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([203, 0, 113, 7], 50000))));
+        if let Some(actor) = actor {
+            request.extensions_mut().insert(Actor(actor.to_string()));
+        }
+        request
+    }
+
+    #[test]
+    fn only_authenticated_actors_get_a_user_bucket() {
+        assert_eq!(client_key(request(None).extensions()), "ip:203.0.113.7");
+        assert_eq!(
+            client_key(request(Some("desktop")).extensions()),
+            "user:desktop"
+        );
+    }
+
+    #[test]
+    fn eviction_keeps_partially_drained_buckets_of_other_scopes() {
+        let limiter = RateLimiter::new(true);
+        let now = Instant::now();
+        {
+            let mut buckets = limiter.buckets.lock().unwrap();
+            for client in 0..MAX_TRACKED_BUCKETS - 1 {
+                buckets.insert(
+                    ("search", format!("ip:idle-{client}")),
+                    Bucket {
+                        tokens: SEARCH.capacity(),
+                        updated_at: now,
+                        policy: SEARCH,
+                    },
+                );
+            }
+            buckets.insert(
+                ("search", "ip:busy".to_string()),
+                Bucket {
+                    tokens: 10.0,
+                    updated_at: now,
+                    policy: SEARCH,
+                },
+            );
+        }
+        assert!(
+            limiter
+                .check("import", "ip:other".to_string(), IMPORT)
+                .allowed
+        );
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.len(), 2);
+        assert!(buckets[&("search", "ip:busy".to_string())].tokens < 11.0);
+    }
+
+    #[tokio::test]
+    async fn scoped_denials_refund_the_global_token() {
+        let limiter = RateLimiter::new(true);
+        let app = Router::new()
+            .route(
+                "/search",
+                get(|| async {}).layer(limiter.layer("search", ONE)),
+            )
+            .layer(limiter.layer("global", SEARCH));
+        for expected in [StatusCode::OK, StatusCode::TOO_MANY_REQUESTS] {
+            let response = app.clone().oneshot(request(None)).await.unwrap();
+            assert_eq!(response.status(), expected);
+        }
+        let buckets = limiter.buckets();
+        let global = &buckets[&("global", "ip:203.0.113.7".to_string())];
+        assert!(global.tokens > SEARCH.capacity() - 1.5);
+    }
+
+    #[test]
+    fn websocket_messages_charge_the_global_and_search_buckets() {
+        let limiter = RateLimiter::new(true);
+        let messages = limiter.messages(
+            RateLimitPolicy {
+                per_minute: 1,
+                burst: 2,
+            },
+            ONE,
+        );
+        assert!(messages.check("user:desktop", true).is_ok());
+        assert!(matches!(
+            messages.check("user:desktop", true),
+            Err(ApiError::RateLimited)
+        ));
+        assert!(messages.check("user:desktop", false).is_ok());
+        assert!(messages.check("user:desktop", false).is_err());
+        assert!(messages.check("user:mobile", false).is_ok());
+    }
+}
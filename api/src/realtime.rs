@@ -0,0 +1,184 @@
+use crate::context::RequestContext;
+use crate::error::ApiError;
+use crate::events::{Notification, Subscription};
+use crate::rate_limit::MessageLimiter;
+use crate::state::AppState;
+use api_types::realtime::{ClientMessage, ClientRequest, ServerMessage};
+use axum::extract::ws::{CloseFrame, Message, WebSocket, close_code};
+
+pub struct Limits {
+    pub limiter: MessageLimiter,
+    pub client: String,
+}
+
+pub async fn serve(
+    mut socket: WebSocket,
+    state: AppState,
+    context: RequestContext,
+    limits: Limits,
+) {
+    let mut subscription: Option<Subscription> = None;
+    loop {
+        let reply = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    handle(&state, &context, &limits, &text, &mut subscription).await
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+            notification = next_notification(&mut subscription) => match notification {
+                Some(Notification::Event(event)) => ServerMessage::Event { event },
+                Some(Notification::Reset) => ServerMessage::Reset,
+                None => {
+                    subscription = None;
+                    continue;
+                }
+            },
+            _ = state.shutdown.cancelled() => {
+                let _ = socket
+                    .send(Message::Close(Some(CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "Server shutting down".into(),
+                    })))
+                    .await;
+                return;
+            }
+        };
+        let Ok(text) = serde_json::to_string(&reply) else {
+            continue;
+        };
+        if socket.send(Message::Text(text.into())).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn next_notification(subscription: &mut Option<Subscription>) -> Option<Notification> {
+    match subscription {
+        Some(subscription) => subscription.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn handle(
+    state: &AppState,
+    context: &RequestContext,
+    limits: &Limits,
+    text: &str,
+    subscription: &mut Option<Subscription>,
+) -> ServerMessage {
+    let message = serde_json::from_str::<ClientMessage>(text);
+    let search = matches!(
+        &message,
+        Ok(ClientMessage {
+            request: ClientRequest::Search { .. },
+            ..
+        })
+    );
+    if let Err(error) = limits.limiter.check(&limits.client, search) {
+        return error_message(message.ok().map(|message| message.request_id), error);
+    }
+    let message = match message {
+        Ok(message) => message,
+        Err(error) => {
+            return error_message(
+                None,
+                ApiError::BadRequest(format!("Invalid message: {error}")),
+            );
+        }
+    };
+    let request_id = message.request_id;
+    dispatch(state, context, request_id, message.request, subscription)
+        .await
+        .unwrap_or_else(|error| error_message(Some(request_id), error))
+}
+
+async fn dispatch(
+    state: &AppState,
+    context: &RequestContext,
+    request_id: u32,
+    request: ClientRequest,
+    subscription: &mut Option<Subscription>,
+) -> Result<ServerMessage, ApiError> {
+    let database = &state.database;
+    Ok(match request {
+        ClientRequest::List { query } => ServerMessage::Resources {
+            request_id,
+            data: database.list_resources(&query, false).await?,
+        },
+        ClientRequest::Search { query } => ServerMessage::SearchResults {
+            request_id,
+            data: database.search_resources(&query).await?,
+        },
+        ClientRequest::Get { id } => ServerMessage::Resource {
+            request_id,
+            data: database
+                .get_resource(&id)
+                .await?
+                .ok_or(ApiError::NotFound)?,
+        },
+        ClientRequest::Create { input } => ServerMessage::Resource {
+            request_id,
+            data: database.create_resource(input.validate()?, context).await?,
+        },
+        ClientRequest::Update {
+            id,
+            expected_version,
+            changes,
+        } => {
+            let changes = changes.validate()?;
+            require_version(state, expected_version)?;
+            ServerMessage::Resource {
+                request_id,
+                data: database
+                    .update_resource(&id, changes, expected_version, context)
+                    .await?
+                    .ok_or(ApiError::NotFound)?,
+            }
+        }
+        ClientRequest::Delete {
+            id,
+            expected_version,
+        } => {
+            require_version(state, expected_version)?;
+            if !database
+                .delete_resource(&id, expected_version, context)
+                .await?
+            {
+                return Err(ApiError::NotFound);
+            }
+            ServerMessage::Ack { request_id }
+        }
+        ClientRequest::Restore { id } => ServerMessage::Resource {
+            request_id,
+            data: database
+                .restore_resource(&id, context)
+                .await?
+                .ok_or(ApiError::NotFound)?,
+        },
+        ClientRequest::Subscribe { last_event_id } => {
+            *subscription = Some(Subscription::new(database, last_event_id).await?);
+            ServerMessage::Ack { request_id }
+        }
+        ClientRequest::Unsubscribe => {
+            *subscription = None;
+            ServerMessage::Ack { request_id }
+        }
+    })
+}
+
+fn require_version(state: &AppState, expected_version: Option<i64>) -> Result<(), ApiError> {
+    if expected_version.is_none() && state.config.require_if_match {
+        return Err(ApiError::PreconditionRequired);
+    }
+    Ok(())
+}
+
+fn error_message(request_id: Option<u32>, error: ApiError) -> ServerMessage {
+    ServerMessage::Error {
+        request_id,
+        status: error.status().as_u16(),
+        error: error.into_body(),
+    }
+}
@@ -0,0 +1,84 @@
+use crate::context::REQUEST_ID_HEADER;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::HeaderValue;
+use axum::response::Response;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+use uuid::Uuid;
+
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(String::clone).ok()
+}
+
+pub fn from_request<B>(request: &Request<B>) -> &str {
+    request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+fn accept(value: &HeaderValue) -> Option<String> {
+    value
+        .to_str()
+        .ok()
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LENGTH)
+        .filter(|id| id.bytes().all(|byte| byte.is_ascii_graphic()))
+        .map(str::to_string)
+}
+
+#[derive(Clone)]
+pub struct RequestIdLayer;
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestIdMiddleware<S>;
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdMiddleware { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct RequestIdMiddleware<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for RequestIdMiddleware<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, context: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(context)
+    }
+
+    fn call(&mut self, mut request: Request<Body>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let request_id = request
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(accept)
+            .unwrap_or_else(|| Uuid::now_v7().to_string());
+        let header = HeaderValue::from_str(&request_id).expect("request ids are visible ASCII");
+        request
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER, header.clone());
+        Box::pin(REQUEST_ID.scope(request_id, async move {
+            let mut response = inner.call(request).await?;
+            response.headers_mut().insert(REQUEST_ID_HEADER, header);
+            Ok(response)
+        }))
+    }
+}
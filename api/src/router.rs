@@ -1,33 +1,179 @@
+use crate::config::Config;
+use crate::cors;
 use crate::handlers;
-use crate::middleware::AuthLayer;
+use crate::idempotency::IdempotencyLayer;
+use crate::middleware::{AdminLayer, AuthLayer};
+use crate::rate_limit::{RateLimitPolicy, RateLimiter};
+use crate::request_id::{self, RequestIdLayer};
 use crate::state::AppState;
-use axum::Router;
-use axum::routing::get;
-use tower_http::cors::{Any, CorsLayer};
+use crate::telemetry;
+use axum::body::Body;
+use axum::extract::{DefaultBodyLimit, MatchedPath, Request};
+use axum::routing::{MethodRouter, delete, get, post};
+use axum::{Extension, Router};
+use chrono::TimeDelta;
+use std::convert::Infallible;
 use tower_http::trace::TraceLayer;
+use utoipa_swagger_ui::{Config as SwaggerConfig, SwaggerUi};
 
-pub fn create_router(state: AppState) -> Router {
-    let api = Router::new()
-        .route(
+pub const IMPORT_BODY_LIMIT: usize = 32 * 1024 * 1024;
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+pub fn matched_route<B>(request: &Request<B>) -> &str {
+    request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or(UNMATCHED_ROUTE)
+}
+
+fn global_policy(config: &Config) -> RateLimitPolicy {
+    RateLimitPolicy {
+        per_minute: config.rate_limit_per_minute,
+        burst: config.rate_limit_burst,
+    }
+}
+
+pub(crate) fn api_routes(
+    config: &Config,
+    limiter: &RateLimiter,
+) -> Vec<(&'static str, MethodRouter<AppState>)> {
+    let policy = |per_minute: u32| RateLimitPolicy {
+        per_minute,
+        burst: config.rate_limit_burst.min(per_minute),
+    };
+    let search = policy(config.search_rate_limit_per_minute);
+    let admin = AdminLayer::new(&config.admin_actors);
+    vec![
+        (
             "/resources",
             get(handlers::list_resources).post(handlers::create_resource),
-        )
-        .route(
+        ),
+        (
+            "/resources/batch",
+            post(handlers::batch_create)
+                .patch(handlers::batch_update)
+                .delete(handlers::batch_delete),
+        ),
+        (
+            "/resources/search",
+            get(handlers::search_resources).layer(limiter.layer("search", search)),
+        ),
+        ("/resources/events", get(handlers::resource_events)),
+        ("/resources/export", get(handlers::export_resources)),
+        (
+            "/resources/import",
+            post(handlers::import_resources)
+                .layer::<_, Infallible>(
+                    limiter.layer("import", policy(config.import_rate_limit_per_minute)),
+                )
+                .layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+        ),
+        ("/resources/trash", get(handlers::list_trash)),
+        ("/resources/trash/{id}", delete(handlers::purge_resource)),
+        ("/resources/{id}/restore", post(handlers::restore_resource)),
+        ("/resources/{id}/history", get(handlers::resource_history)),
+        ("/resources/{id}/revisions", get(handlers::list_revisions)),
+        (
+            "/resources/{id}/revisions/diff",
+            get(handlers::diff_revisions),
+        ),
+        (
+            "/resources/{id}/revisions/{revision}/restore",
+            post(handlers::restore_revision),
+        ),
+        (
+            "/admin/audit",
+            get(handlers::list_audit_entries).route_layer(admin.clone()),
+        ),
+        (
+            "/ws",
+            get(handlers::websocket)
+                .layer(Extension(limiter.messages(global_policy(config), search))),
+        ),
+        (
+            "/webhooks",
+            get(handlers::list_webhooks)
+                .post(handlers::create_webhook)
+                .route_layer(admin.clone()),
+        ),
+        (
+            "/webhooks/dead-letters",
+            get(handlers::list_dead_letters).route_layer(admin.clone()),
+        ),
+        (
+            "/webhooks/deliveries/{id}/retry",
+            post(handlers::retry_delivery).route_layer(admin.clone()),
+        ),
+        (
+            "/webhooks/{id}",
+            get(handlers::get_webhook)
+                .patch(handlers::update_webhook)
+                .delete(handlers::delete_webhook)
+                .route_layer(admin.clone()),
+        ),
+        (
+            "/webhooks/{id}/deliveries",
+            get(handlers::list_webhook_deliveries).route_layer(admin),
+        ),
+        (
             "/resources/{id}",
             get(handlers::get_resource)
-                .put(handlers::update_resource)
+                .put(handlers::replace_resource)
+                .patch(handlers::patch_resource)
                 .delete(handlers::delete_resource),
-        )
-        .layer(AuthLayer::new(state.config.auth_enabled));
-    Router::new()
-        .route("/health", get(handlers::health_check))
-        .nest("/api/v1", api)
-        .layer(TraceLayer::new_for_http())
+        ),
+    ]
+}
+
+fn api_router(state: &AppState) -> Router<AppState> {
+    let config = &state.config;
+    let limiter = RateLimiter::new(config.rate_limit_enabled);
+    api_routes(config, &limiter)
+        .into_iter()
+        .fold(Router::new(), |router, (path, route)| {
+            router.route(path, route)
+        })
+        .layer(IdempotencyLayer::new(
+            state.database.clone(),
+            TimeDelta::seconds(state.config.idempotency_ttl_secs),
+        ))
+        .layer(limiter.layer("global", global_policy(config)))
+        .layer(AuthLayer::new(
+            state.config.auth_enabled,
+            &state.config.api_keys,
+        ))
+}
+
+pub fn create_router(state: AppState) -> Router {
+    let mut router = Router::new()
+        .route("/health", get(handlers::health_live))
+        .route("/health/live", get(handlers::health_live))
+        .route("/health/ready", get(handlers::health_ready))
+        .route("/metrics", get(handlers::metrics))
+        .route("/api/v1/openapi.json", get(handlers::openapi_spec))
+        .nest("/api/v1", api_router(&state));
+    if state.config.docs_enabled {
+        router = router.merge(
+            SwaggerUi::new("/api/v1/docs").config(SwaggerConfig::new(["/api/v1/openapi.json"])),
+        );
+    }
+    router
+        .layer(state.metrics.layer())
         .layer(
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any),
+            TraceLayer::new_for_http().make_span_with(|request: &Request<Body>| {
+                let span = tracing::info_span!(
+                    "request",
+                    otel.name = %format_args!("{} {}", request.method(), matched_route(request)),
+                    method = %request.method(),
+                    uri = %request.uri(),
+                    request_id = %request_id::from_request(request),
+                );
+                telemetry::set_remote_parent(&span, request.headers());
+                span
+            }),
         )
+        .layer(cors::cors_layer(&state.config.cors))
+        .layer(RequestIdLayer)
         .with_state(state)
 }
@@ -0,0 +1,61 @@
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+pub fn listen() -> CancellationToken {
+    let token = CancellationToken::new();
+    let trigger = token.clone();
+    tokio::spawn(async move {
+        signal().await;
+        tracing::info!("Shutdown signal received, draining connections");
+        trigger.cancel();
+    });
+    token
+}
+
+async fn signal() {
+    let interrupt = async {
+        if let Err(error) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for SIGINT: {error}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(error) => {
+                tracing::error!("Failed to listen for SIGTERM: {error}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+
+pub async fn stop_task(
+    name: &str,
+    token: CancellationToken,
+    mut handle: JoinHandle<()>,
+    timeout: Duration,
+) {
+    token.cancel();
+    match tokio::time::timeout(timeout, &mut handle).await {
+        Ok(Ok(())) => tracing::info!("Stopped {name}"),
+        Ok(Err(error)) => tracing::error!("{name} ended abnormally: {error}"),
+        Err(_) => {
+            tracing::warn!(
+                "{name} did not stop within {}s, aborting",
+                timeout.as_secs()
+            );
+            handle.abort();
+        }
+    }
+}
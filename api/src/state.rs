@@ -1,8 +1,12 @@
 use crate::config::Config;
 use crate::db::SqliteDatabase;
+use crate::metrics::Metrics;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Clone)]
 pub struct AppState {
     pub database: SqliteDatabase,
     pub config: Config,
+    pub metrics: Metrics,
+    pub shutdown: CancellationToken,
 }
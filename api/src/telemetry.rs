@@ -0,0 +1,194 @@
+use crate::config::{Config, LogFormat, OtlpProtocol, TelemetryConfig};
+use axum::http::HeaderMap;
+use opentelemetry::global;
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::filter::{EnvFilter, LevelFilter, Targets};
+use tracing_subscriber::{Layer, layer::SubscriberExt, util::SubscriberInitExt};
+
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Telemetry {
+    pub fn shutdown(self) {
+        if let Some(provider) = self.provider
+            && let Err(error) = provider.shutdown()
+        {
+            tracing::warn!("Failed to flush trace exporter: {error}");
+        }
+    }
+}
+
+pub fn init(config: &Config) -> Result<Telemetry, Box<dyn std::error::Error>> {
+    let fmt = tracing_subscriber::fmt::layer().with_writer(std::io::stderr);
+    let fmt = match config.log_format {
+        LogFormat::Json => fmt.json().boxed(),
+        LogFormat::Text => fmt.boxed(),
+    };
+    let provider = config
+        .telemetry
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| tracer_provider(&config.telemetry, endpoint))
+        .transpose()?;
+    let otel = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+            .with_filter(
+                Targets::new()
+                    .with_default(LevelFilter::INFO)
+                    .with_target("sqlx::query", LevelFilter::DEBUG),
+            )
+    });
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    tracing_subscriber::registry()
+        .with(fmt.with_filter(EnvFilter::from_default_env()))
+        .with(otel)
+        .init();
+    if let Some(endpoint) = &config.telemetry.otlp_endpoint {
+        tracing::info!("Exporting traces to {endpoint}");
+    }
+    Ok(Telemetry { provider })
+}
+
+fn tracer_provider(
+    config: &TelemetryConfig,
+    endpoint: &str,
+) -> Result<SdkTracerProvider, Box<dyn std::error::Error>> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_protocol(match config.otlp_protocol {
+            OtlpProtocol::Protobuf => Protocol::HttpBinary,
+            OtlpProtocol::Json => Protocol::HttpJson,
+        })
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()?;
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build())
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+pub fn set_remote_parent(span: &Span, headers: &HeaderMap) {
+    let context =
+        global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+    let _ = span.set_parent(context);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::db::SqliteDatabase;
+    use crate::metrics::Metrics;
+    use crate::router::create_router;
+    use crate::state::AppState;
+    use axum::Router;
+    use axum::body::{Body, Bytes};
+    use axum::http::{Request, StatusCode};
+    use axum::routing::post;
+    use tokio::sync::mpsc;
+    use tokio_util::sync::CancellationToken;
+    use tower::ServiceExt;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const PARENT_SPAN_ID: &str = "00f067aa0ba902b7";
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn request_spans_are_exported_under_the_incoming_traceparent() {
+        let (sender, mut exported) = mpsc::unbounded_channel();
+        let collector = Router::new().route(
+            "/v1/traces",
+            post(move |body: Bytes| {
+                let _ = sender.send(body);
+                async { StatusCode::OK }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, collector).await });
+
+        let config = TelemetryConfig {
+            otlp_protocol: OtlpProtocol::Json,
+            ..TelemetryConfig::default()
+        };
+        let provider = tokio::task::spawn_blocking(move || {
+            tracer_provider(&config, &endpoint).map_err(|error| error.to_string())
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        let database = SqliteDatabase::new(&DatabaseConfig {
+            url: "sqlite::memory:".to_string(),
+            ..DatabaseConfig::default()
+        })
+        .await
+        .unwrap();
+        let router = create_router(AppState {
+            database,
+            config: Config::default(),
+            metrics: Metrics::default(),
+            shutdown: CancellationToken::new(),
+        });
+        {
+            let _guard = tracing::subscriber::set_default(subscriber);
+            let response = router
+                .oneshot(
+                    Request::get("/health/live")
+                        .header("traceparent", format!("00-{TRACE_ID}-{PARENT_SPAN_ID}-01"))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        tokio::task::spawn_blocking(move || provider.shutdown())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let payload: serde_json::Value =
+            serde_json::from_slice(&exported.recv().await.unwrap()).unwrap();
+        let span = payload["resourceSpans"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|resource| resource["scopeSpans"].as_array().into_iter().flatten())
+            .flat_map(|scope| scope["spans"].as_array().into_iter().flatten())
+            .find(|span| span["name"] == "GET /health/live")
+            .expect("request span was exported");
+        assert_eq!(span["traceId"], TRACE_ID);
+        assert_eq!(span["parentSpanId"], PARENT_SPAN_ID);
+    }
+}
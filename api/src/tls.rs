@@ -0,0 +1,184 @@
+use crate::config::TlsConfig;
+use axum::Router;
+use axum::extract::Request;
+use axum::extract::connect_info::IntoMakeServiceWithConnectInfo;
+use axum::http::uri::{Authority, PathAndQuery};
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum_server::Handle;
+use axum_server::tls_rustls::RustlsConfig;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+pub struct Tls {
+    rustls: RustlsConfig,
+    cert_path: PathBuf,
+    key_path: PathBuf,
+}
+
+impl Tls {
+    pub async fn load(config: &TlsConfig) -> std::io::Result<Option<Self>> {
+        let (Some(cert_path), Some(key_path)) = (&config.cert_path, &config.key_path) else {
+            return Ok(None);
+        };
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let rustls = RustlsConfig::from_pem_file(cert_path, key_path).await?;
+        Ok(Some(Self {
+            rustls,
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+        }))
+    }
+
+    pub async fn serve(
+        &self,
+        listener: tokio::net::TcpListener,
+        app: IntoMakeServiceWithConnectInfo<Router, SocketAddr>,
+        shutdown: CancellationToken,
+    ) -> std::io::Result<()> {
+        let handle = Handle::new();
+        let trigger = handle.clone();
+        tokio::spawn(async move {
+            shutdown.cancelled().await;
+            trigger.graceful_shutdown(None);
+        });
+        axum_server::from_tcp_rustls(listener.into_std()?, self.rustls.clone())?
+            .handle(handle)
+            .serve(app)
+            .await
+    }
+
+    pub fn spawn_reload(&self, interval: Duration, cancel: CancellationToken) -> JoinHandle<()> {
+        let rustls = self.rustls.clone();
+        let cert_path = self.cert_path.clone();
+        let key_path = self.key_path.clone();
+        tokio::spawn(async move {
+            let modified = || {
+                [&cert_path, &key_path].map(|path| {
+                    std::fs::metadata(path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                })
+            };
+            let mut last: [Option<SystemTime>; 2] = modified();
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = cancel.cancelled() => return,
+                }
+                let current = modified();
+                if current == last {
+                    continue;
+                }
+                match rustls.reload_from_pem_file(&cert_path, &key_path).await {
+                    Ok(()) => {
+                        tracing::info!("Reloaded TLS certificate from {}", cert_path.display());
+                        last = current;
+                    }
+                    Err(error) => {
+                        tracing::warn!("Keeping previous TLS certificate, reload failed: {error}")
+                    }
+                }
+            }
+        })
+    }
+}
+
+pub async fn spawn_https_redirect(
+    bind: SocketAddr,
+    https_port: u16,
+    cancel: CancellationToken,
+) -> std::io::Result<JoinHandle<()>> {
+    let listener = tokio::net::TcpListener::bind(bind).await?;
+    let router = Router::new()
+        .fallback(move |request: Request| async move { redirect(&request, https_port) });
+    tracing::info!("Redirecting HTTP on {bind} to HTTPS");
+    Ok(tokio::spawn(async move {
+        if let Err(error) = axum::serve(listener, router)
+            .with_graceful_shutdown(cancel.cancelled_owned())
+            .await
+        {
+            tracing::error!("HTTPS redirect server failed: {error}");
+        }
+    }))
+}
+
+fn redirect(request: &Request, https_port: u16) -> Response {
+    let Some(authority) = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .and_then(|host| host.parse::<Authority>().ok())
+    else {
+        return (StatusCode::BAD_REQUEST, "Missing Host header").into_response();
+    };
+    let path = request
+        .uri()
+        .path_and_query()
+        .map(PathAndQuery::as_str)
+        .unwrap_or("/");
+    let location = match https_port {
+        443 => format!("https://{}{path}", authority.host()),
+        port => format!("https://{}:{port}{path}", authority.host()),
+    };
+    Redirect::permanent(&location).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn location(uri: &str, host: Option<&str>, https_port: u16) -> Response {
+        let mut request = Request::builder().uri(uri);
+        if let Some(host) = host {
+            request = request.header(header::HOST, host);
+        }
+        redirect(&request.body(Body::empty()).unwrap(), https_port)
+    }
+
+    fn assert_redirects(uri: &str, host: &str, https_port: u16, expected: &str) {
+        let response = location(uri, Some(host), https_port);
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(response.headers()[header::LOCATION], expected);
+    }
+
+    #[test]
+    fn redirects_keep_path_and_query_and_replace_the_port() {
+        assert_redirects(
+            "/api/v1/resources?limit=5",
+            "example.com:8080",
+            8443,
+            "https://example.com:8443/api/v1/resources?limit=5",
+        );
+        assert_redirects("/", "example.com", 443, "https://example.com/");
+        assert_redirects(
+            "/health",
+            "example.com:80",
+            443,
+            "https://example.com/health",
+        );
+        assert_redirects("/", "[::1]:8080", 8443, "https://[::1]:8443/");
+        assert_redirects("/x", "[2001:db8::1]", 443, "https://[2001:db8::1]/x");
+    }
+
+    #[test]
+    fn redirect_requires_a_valid_host() {
+        assert_eq!(location("/", None, 443).status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            location("/", Some("bad host"), 443).status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn redirect_listener_fails_when_the_port_is_taken() {
+        let taken = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let result =
+            spawn_https_redirect(taken.local_addr().unwrap(), 443, CancellationToken::new()).await;
+        assert!(result.is_err());
+    }
+}
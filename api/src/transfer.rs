@@ -0,0 +1,254 @@
+use crate::error::ApiError;
+use api_types::resources::{CreateResource, Resource};
+use api_types::transfer::{ImportRecord, TransferFormat};
+use chrono::SecondsFormat;
+use futures_util::{Stream, StreamExt, stream};
+use serde_json::value::RawValue;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+const CSV_HEADER: [&str; 6] = [
+    "id",
+    "name",
+    "description",
+    "created_at",
+    "updated_at",
+    "version",
+];
+
+pub struct ImportRow {
+    pub line: usize,
+    pub id: Option<String>,
+    pub input: Result<CreateResource, ApiError>,
+}
+
+pub struct Encoder {
+    format: TransferFormat,
+    first: bool,
+}
+
+impl Encoder {
+    pub fn new(format: TransferFormat) -> Self {
+        Self {
+            format,
+            first: true,
+        }
+    }
+
+    pub fn header(&self) -> Result<String, ApiError> {
+        match self.format {
+            TransferFormat::Csv => csv_line(CSV_HEADER),
+            TransferFormat::Json => Ok("[".to_string()),
+            TransferFormat::Ndjson => Ok(String::new()),
+        }
+    }
+
+    pub fn encode(&mut self, resource: &Resource) -> Result<String, ApiError> {
+        let first = std::mem::replace(&mut self.first, false);
+        match self.format {
+            TransferFormat::Csv => csv_line([
+                resource.id.as_str(),
+                resource.name.as_str(),
+                resource.description.as_deref().unwrap_or_default(),
+                &resource
+                    .created_at
+                    .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                &resource
+                    .updated_at
+                    .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                &resource.version.to_string(),
+            ]),
+            TransferFormat::Json => {
+                let separator = if first { "\n" } else { ",\n" };
+                Ok(format!("{separator}{}", to_json(resource)?))
+            }
+            TransferFormat::Ndjson => Ok(format!("{}\n", to_json(resource)?)),
+        }
+    }
+
+    pub fn footer(&self) -> String {
+        match self.format {
+            TransferFormat::Json if self.first => "]\n".to_string(),
+            TransferFormat::Json => "\n]\n".to_string(),
+            TransferFormat::Csv | TransferFormat::Ndjson => String::new(),
+        }
+    }
+}
+
+pub fn encode_stream(
+    format: TransferFormat,
+    resources: mpsc::Receiver<Result<Resource, ApiError>>,
+) -> impl Stream<Item = Result<String, ApiError>> + Send + 'static {
+    stream::unfold(
+        (Some(Encoder::new(format)), resources, true),
+        |(encoder, mut resources, started)| async move {
+            let mut encoder = encoder?;
+            if started {
+                let header = encoder.header();
+                return Some((header, (Some(encoder), resources, false)));
+            }
+            match resources.recv().await {
+                Some(Ok(resource)) => {
+                    let chunk = encoder.encode(&resource);
+                    Some((chunk, (Some(encoder), resources, false)))
+                }
+                Some(Err(error)) => Some((Err(error), (None, resources, false))),
+                None => Some((Ok(encoder.footer()), (None, resources, false))),
+            }
+        },
+    )
+    .filter(|chunk| std::future::ready(!matches!(chunk, Ok(chunk) if chunk.is_empty())))
+}
+
+type ParsedRecord = (usize, Result<ImportRecord, ApiError>);
+
+pub fn parse_import(format: TransferFormat, body: &str) -> Result<Vec<ImportRow>, ApiError> {
+    let records = match format {
+        TransferFormat::Csv => parse_csv(body)?,
+        TransferFormat::Json => {
+            let items: Vec<&RawValue> = serde_json::from_str(body)
+                .map_err(|error| ApiError::BadRequest(format!("Invalid JSON array: {error}")))?;
+            items
+                .into_iter()
+                .map(|item| {
+                    let offset = item.get().as_ptr() as usize - body.as_ptr() as usize;
+                    (
+                        body[..offset].matches('\n').count() + 1,
+                        serde_json::from_str(item.get()).map_err(invalid_record),
+                    )
+                })
+                .collect()
+        }
+        TransferFormat::Ndjson => body
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                (
+                    index + 1,
+                    serde_json::from_str(line).map_err(invalid_record),
+                )
+            })
+            .collect(),
+    };
+    Ok(records
+        .into_iter()
+        .map(|(line, record)| match record {
+            Ok(ImportRecord {
+                id,
+                name,
+                description,
+            }) => {
+                let id = id.filter(|id| !id.trim().is_empty());
+                match id.as_deref().map(parse_id).transpose() {
+                    Ok(parsed) => ImportRow {
+                        line,
+                        id: parsed,
+                        input: CreateResource { name, description }
+                            .validate()
+                            .map_err(ApiError::from),
+                    },
+                    Err(error) => ImportRow {
+                        line,
+                        id,
+                        input: Err(error),
+                    },
+                }
+            }
+            Err(error) => ImportRow {
+                line,
+                id: None,
+                input: Err(error),
+            },
+        })
+        .collect())
+}
+
+fn parse_csv(body: &str) -> Result<Vec<ParsedRecord>, ApiError> {
+    let mut reader = csv::Reader::from_reader(body.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|error| ApiError::BadRequest(format!("Invalid CSV header: {error}")))?
+        .clone();
+    let column = |name: &str| headers.iter().position(|header| header.trim() == name);
+    let (id_column, name_column, description_column) =
+        (column("id"), column("name"), column("description"));
+    if name_column.is_none() {
+        return Err(ApiError::BadRequest(
+            "CSV header must include a name column".to_string(),
+        ));
+    }
+    Ok(reader
+        .records()
+        .map(|record| {
+            let position = match &record {
+                Ok(record) => record.position(),
+                Err(error) => error.position(),
+            };
+            let line = position
+                .map(|position| position.line() as usize)
+                .unwrap_or_default();
+            let record = record
+                .map(|record| {
+                    let field = |column: Option<usize>| {
+                        column
+                            .and_then(|column| record.get(column))
+                            .filter(|value| !value.is_empty())
+                            .map(str::to_string)
+                    };
+                    ImportRecord {
+                        id: field(id_column),
+                        name: field(name_column).unwrap_or_default(),
+                        description: field(description_column),
+                    }
+                })
+                .map_err(invalid_record);
+            (line, record)
+        })
+        .collect())
+}
+
+fn parse_id(id: &str) -> Result<String, ApiError> {
+    Uuid::parse_str(id.trim())
+        .map(|id| id.to_string())
+        .map_err(|_| ApiError::BadRequest(format!("Invalid resource id: {id}")))
+}
+
+fn invalid_record(error: impl std::fmt::Display) -> ApiError {
+    ApiError::BadRequest(format!("Invalid record: {error}"))
+}
+
+fn csv_line<const N: usize>(fields: [&str; N]) -> Result<String, ApiError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(fields)
+        .map_err(|error| ApiError::Database(error.to_string()))?;
+    let bytes = writer
+        .into_inner()
+        .map_err(|error| ApiError::Database(error.to_string()))?;
+    String::from_utf8(bytes).map_err(|error| ApiError::Database(error.to_string()))
+}
+
+fn to_json(resource: &Resource) -> Result<String, ApiError> {
+    serde_json::to_string(resource).map_err(|error| ApiError::Database(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_import_reports_source_lines_and_rejects_malformed_ids() {
+        let body = "[\n  {\"name\": \"first\"},\n\n  {\"id\": \"../etc\", \"name\": \"second\"},\n  {\"id\": \"0192F0A4-7B3C-7D2E-8F10-123456789ABC\", \"name\": \"third\"}\n]";
+        let rows = parse_import(TransferFormat::Json, body).unwrap();
+        let lines: Vec<usize> = rows.iter().map(|row| row.line).collect();
+        assert_eq!(lines, [2, 4, 5]);
+        assert!(rows[0].input.is_ok());
+        assert!(matches!(rows[1].input, Err(ApiError::BadRequest(_))));
+        assert_eq!(rows[1].id.as_deref(), Some("../etc"));
+        assert_eq!(
+            rows[2].id.as_deref(),
+            Some("0192f0a4-7b3c-7d2e-8f10-123456789abc")
+        );
+    }
+}
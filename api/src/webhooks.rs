@@ -0,0 +1,480 @@
+use crate::db::{DueDelivery, SqliteDatabase};
+use crate::error::ApiError;
+use api_types::validation::FieldError;
+use api_types::webhooks::{
+    DELIVERY_HEADER, DeliveryStatus, EVENT_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+use chrono::{TimeDelta, Utc};
+use futures_util::{StreamExt, future, stream};
+use hmac::{Hmac, Mac};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{Url, redirect};
+use sha2::Sha256;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+const SIGNATURE_PREFIX: &str = "sha256=";
+const DELIVERY_BATCH_SIZE: u32 = 50;
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(3600);
+const DELIVERY_CONCURRENCY: usize = 8;
+#[cfg(any(test, feature = "dev-tools"))]
+const SIGNATURE_TOLERANCE_SECS: u64 = 300;
+const METADATA_ADDRESSES: [IpAddr; 2] = [
+    IpAddr::V4(Ipv4Addr::new(100, 100, 100, 200)),
+    IpAddr::V6(Ipv6Addr::new(0xfd00, 0xec2, 0, 0, 0, 0, 0, 0x254)),
+];
+
+#[derive(Clone)]
+pub struct WebhookSettings {
+    pub max_attempts: u32,
+    pub retry_base: Duration,
+    pub timeout: Duration,
+    pub allow_loopback: bool,
+}
+
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    format!(
+        "{SIGNATURE_PREFIX}{}",
+        hex::encode(mac(secret, timestamp, body).finalize().into_bytes())
+    )
+}
+
+#[cfg(any(test, feature = "dev-tools"))]
+pub fn verify(secret: &str, timestamp: i64, body: &str, signature: &str) -> bool {
+    verify_at(secret, timestamp, body, signature, Utc::now().timestamp())
+}
+
+#[cfg(any(test, feature = "dev-tools"))]
+fn verify_at(secret: &str, timestamp: i64, body: &str, signature: &str, now: i64) -> bool {
+    now.abs_diff(timestamp) <= SIGNATURE_TOLERANCE_SECS
+        && signature
+            .strip_prefix(SIGNATURE_PREFIX)
+            .and_then(|signature| hex::decode(signature).ok())
+            .is_some_and(|signature| {
+                mac(secret, timestamp, body)
+                    .verify_slice(&signature)
+                    .is_ok()
+            })
+}
+
+fn mac(secret: &str, timestamp: i64, body: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{timestamp}.{body}").as_bytes());
+    mac
+}
+
+fn backoff(settings: &WebhookSettings, attempts: u32) -> Duration {
+    settings
+        .retry_base
+        .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX_BACKOFF)
+}
+
+fn blocked_address(ip: IpAddr, allow_loopback: bool) -> bool {
+    let ip = ip.to_canonical();
+    if ip.is_loopback() {
+        return !allow_loopback;
+    }
+    ip.is_unspecified()
+        || METADATA_ADDRESSES.contains(&ip)
+        || match ip {
+            IpAddr::V4(ip) => ip.is_link_local(),
+            IpAddr::V6(ip) => ip.is_unicast_link_local(),
+        }
+}
+
+fn blocked_target(url: &str, allow_loopback: bool) -> Option<String> {
+    let host = Url::parse(url).ok()?.host_str()?.to_string();
+    let ip = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()?;
+    blocked_address(ip, allow_loopback)
+        .then(|| format!("Refusing to deliver to blocked address {ip}"))
+}
+
+pub async fn validate_target(url: &str, allow_loopback: bool) -> Result<(), ApiError> {
+    let Some((host, port)) = Url::parse(url)
+        .ok()
+        .and_then(|url| Some((url.host_str()?.to_string(), url.port_or_known_default()?)))
+    else {
+        return Ok(());
+    };
+    let addresses: Vec<IpAddr> = match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(ip) => vec![ip],
+        Err(_) => tokio::net::lookup_host((host.as_str(), port))
+            .await
+            .map(|addresses| addresses.map(|address| address.ip()).collect())
+            .unwrap_or_default(),
+    };
+    if addresses
+        .into_iter()
+        .any(|ip| blocked_address(ip, allow_loopback))
+    {
+        return Err(ApiError::Validation(vec![FieldError::new(
+            "url",
+            "URL must not point at loopback, link-local or metadata addresses",
+        )]));
+    }
+    Ok(())
+}
+
+struct GuardedResolver {
+    allow_loopback: bool,
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let allow_loopback = self.allow_loopback;
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|address| !blocked_address(address.ip(), allow_loopback))
+                .collect();
+            if addresses.is_empty() {
+                return Err(format!("{} has no deliverable addresses", name.as_str()).into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn client(settings: &WebhookSettings) -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(settings.timeout)
+        .redirect(redirect::Policy::none())
+        .dns_resolver(Arc::new(GuardedResolver {
+            allow_loopback: settings.allow_loopback,
+        }))
+        .build()
+}
+
+pub fn spawn_webhook_dispatcher(
+    database: SqliteDatabase,
+    settings: WebhookSettings,
+    cancel: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let client = match client(&settings) {
+            Ok(client) => client,
+            Err(error) => {
+                tracing::error!("Webhook dispatcher disabled: {error}");
+                return;
+            }
+        };
+        let mut events = database.subscribe();
+        loop {
+            deliver_due(&database, &client, &settings, &cancel).await;
+            tokio::select! {
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                _ = events.recv() => {}
+                _ = cancel.cancelled() => return,
+            }
+        }
+    })
+}
+
+async fn deliver_due(
+    database: &SqliteDatabase,
+    client: &reqwest::Client,
+    settings: &WebhookSettings,
+    cancel: &CancellationToken,
+) {
+    while !cancel.is_cancelled() {
+        let deliveries = match database.due_deliveries(DELIVERY_BATCH_SIZE).await {
+            Ok(deliveries) => deliveries,
+            Err(error) => {
+                tracing::error!("Failed to load webhook deliveries: {error}");
+                return;
+            }
+        };
+        if deliveries.is_empty() {
+            return;
+        }
+        let recorded: Vec<bool> = stream::iter(deliveries)
+            .take_while(|_| future::ready(!cancel.is_cancelled()))
+            .map(|delivery| attempt(database, client, settings, delivery))
+            .buffer_unordered(DELIVERY_CONCURRENCY)
+            .collect()
+            .await;
+        if !recorded.into_iter().all(|recorded| recorded) {
+            return;
+        }
+    }
+}
+
+async fn attempt(
+    database: &SqliteDatabase,
+    client: &reqwest::Client,
+    settings: &WebhookSettings,
+    delivery: DueDelivery,
+) -> bool {
+    let id = delivery.id;
+    let attempts = delivery.attempts + 1;
+    let (response_status, error) = match send(client, settings, delivery).await {
+        Ok(()) => (None, None),
+        Err((status, error)) => (status, Some(error)),
+    };
+    let (status, next_attempt_at) = match &error {
+        None => (DeliveryStatus::Delivered, Utc::now()),
+        Some(_) if attempts >= settings.max_attempts => (DeliveryStatus::Dead, Utc::now()),
+        Some(_) => (
+            DeliveryStatus::Pending,
+            Utc::now() + TimeDelta::from_std(backoff(settings, attempts)).unwrap_or_default(),
+        ),
+    };
+    if let Some(error) = &error {
+        tracing::warn!("Webhook delivery {id} attempt {attempts} failed: {error}");
+    }
+    if let Err(error) = database
+        .record_delivery_attempt(
+            id,
+            status,
+            response_status,
+            error.as_deref(),
+            next_attempt_at,
+        )
+        .await
+    {
+        tracing::error!("Failed to record webhook delivery {id}: {error}");
+        return false;
+    }
+    true
+}
+
+async fn send(
+    client: &reqwest::Client,
+    settings: &WebhookSettings,
+    delivery: DueDelivery,
+) -> Result<(), (Option<u16>, String)> {
+    if let Some(error) = blocked_target(&delivery.url, settings.allow_loopback) {
+        return Err((None, error));
+    }
+    let timestamp = Utc::now().timestamp();
+    let response = client
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .header(EVENT_HEADER, &delivery.event_kind)
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(
+            SIGNATURE_HEADER,
+            sign(&delivery.secret, timestamp, &delivery.payload),
+        )
+        .body(delivery.payload)
+        .send()
+        .await
+        .map_err(|error| (None, error.to_string()))?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err((
+            Some(status.as_u16()),
+            format!("Receiver responded {status}"),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::context::RequestContext;
+    use api_types::resources::CreateResource;
+    use api_types::webhooks::{CreateWebhook, DeliveryQuery};
+    use axum::Router;
+    use axum::http::{HeaderMap, StatusCode};
+    use std::sync::Mutex;
+
+    const SECRET: &str = "receiver-test-secret";
+
+    fn settings() -> WebhookSettings {
+        WebhookSettings {
+            max_attempts: 3,
+            retry_base: Duration::ZERO,
+            timeout: Duration::from_secs(5),
+            allow_loopback: true,
+        }
+    }
+
+    #[test]
+    fn signatures_verify_only_for_the_same_secret_body_and_a_recent_timestamp() {
+        let signature = sign(SECRET, 1_700_000_000, "{}");
+        assert!(verify_at(
+            SECRET,
+            1_700_000_000,
+            "{}",
+            &signature,
+            1_700_000_000
+        ));
+        assert!(verify_at(
+            SECRET,
+            1_700_000_000,
+            "{}",
+            &signature,
+            1_700_000_300
+        ));
+        assert!(!verify_at(
+            SECRET,
+            1_700_000_000,
+            "{}",
+            &signature,
+            1_700_000_301
+        ));
+        assert!(!verify_at(
+            SECRET,
+            1_700_000_000,
+            "{}",
+            &signature,
+            1_699_999_699
+        ));
+        assert!(!verify_at(
+            "other-secret",
+            1_700_000_000,
+            "{}",
+            &signature,
+            1_700_000_000
+        ));
+        assert!(!verify_at(
+            SECRET,
+            1_700_000_000,
+            "[]",
+            &signature,
+            1_700_000_000
+        ));
+        assert!(!verify_at(
+            SECRET,
+            1_700_000_001,
+            "{}",
+            &signature,
+            1_700_000_001
+        ));
+        let unprefixed = signature.trim_start_matches(SIGNATURE_PREFIX);
+        assert!(!verify_at(
+            SECRET,
+            1_700_000_000,
+            "{}",
+            unprefixed,
+            1_700_000_000
+        ));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let settings = WebhookSettings {
+            retry_base: Duration::from_secs(10),
+            ..settings()
+        };
+        let delays: Vec<u64> = [1, 2, 3, 4]
+            .map(|attempts| backoff(&settings, attempts).as_secs())
+            .into();
+        assert_eq!(delays, [10, 20, 40, 80]);
+        assert_eq!(backoff(&settings, 40), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn loopback_link_local_and_metadata_targets_are_blocked() {
+        for blocked in [
+            "http://169.254.169.254/latest/meta-data",
+            "http://[fe80::1]/",
+            "http://[fd00:ec2::254]/",
+            "http://100.100.100.200/",
+            "http://0.0.0.0:8080/",
+            "http://127.0.0.1:4000/",
+            "http://[::1]/",
+            "http://[::ffff:127.0.0.1]/",
+        ] {
+            assert!(blocked_target(blocked, false).is_some(), "{blocked}");
+        }
+        assert!(blocked_target("http://127.0.0.1:4000/", true).is_none());
+        assert!(blocked_target("http://169.254.169.254/", true).is_some());
+        assert!(blocked_target("https://10.0.0.5/hook", false).is_none());
+        assert!(blocked_target("https://example.com/hook", false).is_none());
+    }
+
+    #[tokio::test]
+    async fn deliveries_are_signed_and_retried_until_the_receiver_accepts() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        let receiver = Router::new().fallback(move |headers: HeaderMap, body: String| {
+            let log = log.clone();
+            async move {
+                let header = |name: &str| headers[name].to_str().unwrap().to_string();
+                let verified = verify(
+                    SECRET,
+                    header(TIMESTAMP_HEADER).parse().unwrap(),
+                    &body,
+                    &header(SIGNATURE_HEADER),
+                );
+                let mut log = log.lock().unwrap();
+                log.push((header(DELIVERY_HEADER), header(EVENT_HEADER), verified));
+                if log.len() == 1 {
+                    StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    StatusCode::NO_CONTENT
+                }
+            }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, receiver).await });
+
+        let database = SqliteDatabase::new(&DatabaseConfig {
+            url: "sqlite::memory:".to_string(),
+            max_connections: 1,
+            ..DatabaseConfig::default()
+        })
+        .await
+        .unwrap();
+        database.migrate().await.unwrap();
+        let webhook = database
+            .create_webhook(
+                CreateWebhook {
+                    url: format!("http://{address}/hook"),
+                    events: Vec::new(),
+                    secret: Some(SECRET.to_string()),
+                }
+                .validate()
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        database
+            .create_resource(
+                CreateResource {
+                    name: "delivered".to_string(),
+                    description: None,
+                },
+                &RequestContext::system(),
+            )
+            .await
+            .unwrap();
+
+        let settings = settings();
+        let client = client(&settings).unwrap();
+        deliver_due(&database, &client, &settings, &CancellationToken::new()).await;
+
+        let deliveries = database
+            .list_deliveries(Some(&webhook.id), &DeliveryQuery::default())
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].status, DeliveryStatus::Delivered);
+        assert_eq!(deliveries[0].attempts, 2);
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        for (delivery, event, verified) in received.iter() {
+            assert_eq!(delivery, &deliveries[0].id.to_string());
+            assert_eq!(event, "created");
+            assert!(verified);
+        }
+    }
+}
@@ -1,11 +1,16 @@
-use api_types::resources::{CreateResource, Resource};
-use api_types::responses::{ApiListResponse, ApiResponse};
+use api_types::events::{ResourceEvent, ResourceEventKind, RESET_EVENT};
+use api_types::resources::{CreateResource, Resource, ResourceSearchHit};
+use api_types::responses::{ApiErrorResponse, ApiListResponse, ApiResponse};
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{Request, RequestInit, RequestMode, Response};
+use web_sys::{EventSource, MessageEvent, Request, RequestInit, RequestMode, Response};
 
 const API_BASE: &str = "http://localhost:3000";
 
+pub const CONFLICT_MESSAGE: &str = "Changed by someone else. Refresh and try again.";
+
 pub async fn list_resources() -> Result<Vec<Resource>, String> {
     let response = fetch_json::<ApiListResponse<Resource>>(
         &format!("{API_BASE}/api/v1/resources"),
@@ -16,6 +21,17 @@ pub async fn list_resources() -> Result<Vec<Resource>, String> {
     Ok(response.data)
 }
 
+pub async fn search_resources(query: &str) -> Result<Vec<ResourceSearchHit>, String> {
+    let query = String::from(js_sys::encode_uri_component(query));
+    let response = fetch_json::<ApiListResponse<ResourceSearchHit>>(
+        &format!("{API_BASE}/api/v1/resources/search?q={query}"),
+        "GET",
+        None,
+    )
+    .await?;
+    Ok(response.data)
+}
+
 pub async fn create_resource(
     name: String,
     description: Option<String>,
@@ -31,21 +47,113 @@ pub async fn create_resource(
     Ok(response.data)
 }
 
-pub async fn delete_resource(id: &str) -> Result<(), String> {
-    fetch(&format!("{API_BASE}/api/v1/resources/{id}"), "DELETE", None).await?;
+pub async fn delete_resource(id: &str, version: i64) -> Result<(), String> {
+    fetch(
+        &format!("{API_BASE}/api/v1/resources/{id}"),
+        "DELETE",
+        None,
+        &[("If-Match", format!("\"{version}\""))],
+    )
+    .await?;
     Ok(())
 }
 
+pub async fn restore_resource(id: &str) -> Result<Resource, String> {
+    let response = fetch_json::<ApiResponse<Resource>>(
+        &format!("{API_BASE}/api/v1/resources/{id}/restore"),
+        "POST",
+        None,
+    )
+    .await?;
+    Ok(response.data)
+}
+
+pub struct EventSubscription {
+    source: EventSource,
+    _listeners: Vec<Closure<dyn FnMut(MessageEvent)>>,
+}
+
+impl Drop for EventSubscription {
+    fn drop(&mut self) {
+        self.source.close();
+    }
+}
+
+pub fn subscribe_events(
+    on_event: impl Fn(ResourceEvent) + 'static,
+    on_reset: impl Fn() + 'static,
+) -> Result<EventSubscription, String> {
+    let source = EventSource::new(&format!("{API_BASE}/api/v1/resources/events"))
+        .map_err(|error| format!("{error:?}"))?;
+    let on_event = Rc::new(on_event);
+    let mut listeners = Vec::new();
+    for kind in ResourceEventKind::ALL {
+        let on_event = on_event.clone();
+        listeners.push((
+            kind.as_str(),
+            Closure::<dyn FnMut(MessageEvent)>::new(move |message: MessageEvent| {
+                if let Some(event) = message
+                    .data()
+                    .as_string()
+                    .and_then(|data| serde_json::from_str(&data).ok())
+                {
+                    on_event(event);
+                }
+            }),
+        ));
+    }
+    listeners.push((
+        RESET_EVENT,
+        Closure::<dyn FnMut(MessageEvent)>::new(move |_| on_reset()),
+    ));
+    for (name, listener) in &listeners {
+        source
+            .add_event_listener_with_callback(name, listener.as_ref().unchecked_ref())
+            .map_err(|error| format!("{error:?}"))?;
+    }
+    Ok(EventSubscription {
+        source,
+        _listeners: listeners
+            .into_iter()
+            .map(|(_, listener)| listener)
+            .collect(),
+    })
+}
+
 async fn fetch_json<T: serde::de::DeserializeOwned>(
     url: &str,
     method: &str,
     body: Option<String>,
 ) -> Result<T, String> {
-    let text = fetch(url, method, body).await?;
+    let text = fetch(url, method, body, &[]).await?;
     serde_json::from_str(&text).map_err(|error| error.to_string())
 }
 
-async fn fetch(url: &str, method: &str, body: Option<String>) -> Result<String, String> {
+async fn fetch(
+    url: &str,
+    method: &str,
+    body: Option<String>,
+    headers: &[(&str, String)],
+) -> Result<String, String> {
+    let (status, text) = fetch_response(url, method, body, headers).await?;
+
+    if status == 412 {
+        return Err(CONFLICT_MESSAGE.to_string());
+    }
+
+    if !(200..300).contains(&status) {
+        return Err(error_message(status, &text));
+    }
+
+    Ok(text)
+}
+
+async fn fetch_response(
+    url: &str,
+    method: &str,
+    body: Option<String>,
+    headers: &[(&str, String)],
+) -> Result<(u16, String), String> {
     let opts = RequestInit::new();
     opts.set_method(method);
     opts.set_mode(RequestMode::Cors);
@@ -61,6 +169,12 @@ async fn fetch(url: &str, method: &str, body: Option<String>) -> Result<String,
         .headers()
         .set("Content-Type", "application/json")
         .map_err(|error| format!("{error:?}"))?;
+    for (name, value) in headers {
+        request
+            .headers()
+            .set(name, value)
+            .map_err(|error| format!("{error:?}"))?;
+    }
 
     let window = web_sys::window().ok_or("No window")?;
     let response_value = JsFuture::from(window.fetch_with_request(&request))
@@ -71,13 +185,26 @@ async fn fetch(url: &str, method: &str, body: Option<String>) -> Result<String,
         .dyn_into()
         .map_err(|error| format!("{error:?}"))?;
 
-    if !response.ok() {
-        return Err(format!("HTTP {}", response.status()));
-    }
-
     let text = JsFuture::from(response.text().map_err(|error| format!("{error:?}"))?)
         .await
-        .map_err(|error| format!("{error:?}"))?;
+        .map_err(|error| format!("{error:?}"))?
+        .as_string()
+        .ok_or("Response not a string".to_string())?;
 
-    text.as_string().ok_or("Response not a string".to_string())
+    Ok((response.status(), text))
+}
+
+fn error_message(status: u16, body: &str) -> String {
+    let Ok(error) = serde_json::from_str::<ApiErrorResponse>(body) else {
+        return format!("HTTP {status}");
+    };
+    if error.fields.is_empty() {
+        return error.error;
+    }
+    error
+        .fields
+        .iter()
+        .map(|field| field.message.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
@@ -1,4 +1,7 @@
-use api_types::resources::Resource;
+use api_types::events::{ResourceEvent, ResourceEventKind};
+use api_types::resources::{
+    CreateResource, Resource, ResourceSearchHit, SEARCH_HIGHLIGHT_END, SEARCH_HIGHLIGHT_START,
+};
 use leptos::context::Provider;
 use leptos::prelude::*;
 use leptos::task::spawn_local;
@@ -171,14 +174,18 @@ fn ApiPage() -> impl IntoView {
     let (show_toast, set_show_toast) = signal(false);
     let (toast_message, set_toast_message) = signal(String::new());
     let (toast_type, set_toast_type) = signal(ToastType::Success);
+    let (search_query, set_search_query) = signal(String::new());
+    let (search_results, set_search_results) = signal(None::<Vec<ResourceSearchHit>>);
+    let (undo_target, set_undo_target) = signal(None::<String>);
 
     let show_notification = move |message: String, kind: ToastType| {
+        set_undo_target.set(None);
         set_toast_message.set(message);
         set_toast_type.set(kind);
         set_show_toast.set(true);
     };
 
-    let refresh_resources = move |_| {
+    let load_resources = move || {
         spawn_local(async move {
             match api::list_resources().await {
                 Ok(list) => {
@@ -192,14 +199,69 @@ fn ApiPage() -> impl IntoView {
         });
     };
 
-    let do_create = move || {
-        let name = new_resource_name.get();
-        if name.is_empty() {
-            return;
+    let refresh_resources = move |_| {
+        load_resources();
+    };
+
+    let apply_event = move |event: ResourceEvent| {
+        let id = event.resource_id;
+        match (event.kind, event.resource) {
+            (ResourceEventKind::Created | ResourceEventKind::Restored, Some(resource)) => {
+                set_resources.update(|list| {
+                    if !list.iter().any(|existing| existing.id == id) {
+                        list.insert(0, resource);
+                    }
+                })
+            }
+            (ResourceEventKind::Updated, Some(resource)) => {
+                set_resources.update(|list| {
+                    if let Some(existing) = list.iter_mut().find(|existing| existing.id == id) {
+                        *existing = resource.clone();
+                    }
+                });
+                set_search_results.update(|hits| {
+                    if let Some(hit) = hits.iter_mut().flatten().find(|hit| hit.resource.id == id) {
+                        hit.resource = resource;
+                    }
+                });
+            }
+            (ResourceEventKind::Deleted, _) => {
+                set_resources.update(|list| list.retain(|resource| resource.id != id));
+                set_search_results.update(|hits| {
+                    if let Some(hits) = hits {
+                        hits.retain(|hit| hit.resource.id != id);
+                    }
+                });
+            }
+            _ => {}
         }
+    };
+
+    load_resources();
+    match api::subscribe_events(apply_event, load_resources) {
+        Ok(subscription) => {
+            StoredValue::new_local(subscription);
+        }
+        Err(error) => log::warn!("Live updates unavailable: {error}"),
+    }
+
+    let do_create = move || {
+        let input = CreateResource {
+            name: new_resource_name.get(),
+            description: None,
+        };
+        let CreateResource { name, description } = match input.validate() {
+            Ok(input) => input,
+            Err(errors) => {
+                if let Some(error) = errors.into_iter().next() {
+                    show_notification(error.message, ToastType::Warning);
+                }
+                return;
+            }
+        };
         set_new_resource_name.set(String::new());
         spawn_local(async move {
-            match api::create_resource(name.clone(), None).await {
+            match api::create_resource(name.clone(), description).await {
                 Ok(resource) => {
                     set_resources.update(|list| list.insert(0, resource));
                     show_notification(format!("Created {name}"), ToastType::Success);
@@ -221,12 +283,71 @@ fn ApiPage() -> impl IntoView {
         }
     };
 
-    let delete_resource = move |id: String, name: String| {
+    let do_search = move || {
+        let query = search_query.get();
+        if query.trim().is_empty() {
+            set_search_results.set(None);
+            return;
+        }
         spawn_local(async move {
-            match api::delete_resource(&id).await {
+            match api::search_resources(&query).await {
+                Ok(hits) => set_search_results.set(Some(hits)),
+                Err(error) => {
+                    show_notification(format!("Error: {error}"), ToastType::Error);
+                }
+            }
+        });
+    };
+
+    let search = move |_| {
+        do_search();
+    };
+
+    let on_search_keypress = move |event: web_sys::KeyboardEvent| {
+        if event.key() == "Enter" {
+            do_search();
+        }
+    };
+
+    let clear_search = move |_| {
+        set_search_query.set(String::new());
+        set_search_results.set(None);
+    };
+
+    let delete_resource = move |id: String, name: String, version: i64| {
+        spawn_local(async move {
+            match api::delete_resource(&id, version).await {
                 Ok(()) => {
                     set_resources.update(|list| list.retain(|resource| resource.id != id));
+                    set_search_results.update(|hits| {
+                        if let Some(hits) = hits {
+                            hits.retain(|hit| hit.resource.id != id);
+                        }
+                    });
                     show_notification(format!("Deleted {name}"), ToastType::Success);
+                    set_undo_target.set(Some(id));
+                }
+                Err(error) if error == api::CONFLICT_MESSAGE => {
+                    show_notification(error, ToastType::Warning);
+                }
+                Err(error) => {
+                    show_notification(format!("Error: {error}"), ToastType::Error);
+                }
+            }
+        });
+    };
+
+    let undo_delete = move |_| {
+        let Some(id) = undo_target.get() else {
+            return;
+        };
+        set_undo_target.set(None);
+        spawn_local(async move {
+            match api::restore_resource(&id).await {
+                Ok(resource) => {
+                    let name = resource.name.clone();
+                    set_resources.update(|list| list.insert(0, resource));
+                    show_notification(format!("Restored {name}"), ToastType::Success);
                 }
                 Err(error) => {
                     show_notification(format!("Error: {error}"), ToastType::Error);
@@ -269,8 +390,55 @@ fn ApiPage() -> impl IntoView {
                 </Button>
             </div>
 
+            <div class="flex gap-3 mb-6">
+                <input
+                    type="search"
+                    placeholder="Search name and description"
+                    class="flex-1 px-4 py-3 bg-[#2D3131] text-[#F6F7F5] border border-[#444748] rounded-lg focus:border-[#4EC6F0] outline-none"
+                    prop:value=move || search_query.get()
+                    on:input=move |event| set_search_query.set(event_target_value(&event))
+                    on:keypress=on_search_keypress
+                />
+                <Button variant=ButtonVariant::Secondary on_click=Callback::new(search)>
+                    "Search"
+                </Button>
+                <Show when=move || search_results.with(Option::is_some)>
+                    <Button variant=ButtonVariant::Ghost on_click=Callback::new(clear_search)>
+                        "Clear"
+                    </Button>
+                </Show>
+            </div>
+
             <div class="bg-[#111] rounded-lg p-4 max-h-80 overflow-y-auto">
                 {move || {
+                    if let Some(hits) = search_results.get() {
+                        if hits.is_empty() {
+                            return view! { <div class="text-[#666]">"No matching resources."</div> }.into_any();
+                        }
+                        return hits.into_iter().map(|hit| {
+                            let id = hit.resource.id.clone();
+                            let name = hit.resource.name.clone();
+                            let version = hit.resource.version;
+                            view! {
+                                <div class="flex justify-between items-center py-3 border-b border-[#333]">
+                                    <div>
+                                        <div class="font-medium">{highlighted(hit.name_highlight)}</div>
+                                        {hit.description_snippet.map(|snippet| view! {
+                                            <div class="text-[#A9ACAC] text-sm">{highlighted(snippet)}</div>
+                                        })}
+                                        <div class="text-[#666] text-xs font-mono">{id.clone()}</div>
+                                    </div>
+                                    <Button
+                                        variant=ButtonVariant::Danger
+                                        size=ButtonSize::Small
+                                        on_click=Callback::new(move |_| delete_resource(id.clone(), name.clone(), version))
+                                    >
+                                        "Delete"
+                                    </Button>
+                                </div>
+                            }
+                        }).collect_view().into_any();
+                    }
                     let list = resources.get();
                     if list.is_empty() {
                         view! { <div class="text-[#666]">"No resources. Click Refresh to load."</div> }.into_any()
@@ -280,6 +448,7 @@ fn ApiPage() -> impl IntoView {
                             let name = resource.name.clone();
                             let name_for_delete = resource.name.clone();
                             let id_for_delete = resource.id.clone();
+                            let version = resource.version;
                             view! {
                                 <div class="flex justify-between items-center py-3 border-b border-[#333]">
                                     <div>
@@ -289,7 +458,7 @@ fn ApiPage() -> impl IntoView {
                                     <Button
                                         variant=ButtonVariant::Danger
                                         size=ButtonSize::Small
-                                        on_click=Callback::new(move |_| delete_resource(id_for_delete.clone(), name_for_delete.clone()))
+                                        on_click=Callback::new(move |_| delete_resource(id_for_delete.clone(), name_for_delete.clone(), version))
                                     >
                                         "Delete"
                                     </Button>
@@ -302,14 +471,33 @@ fn ApiPage() -> impl IntoView {
 
             <Toast
                 visible=show_toast
-                toast_type=toast_type.get()
-                title=toast_message.get()
+                toast_type=toast_type
+                title=toast_message
+                action_label=Signal::derive(move || undo_target.with(Option::is_some).then(|| "Undo".to_string()))
+                on_action=Callback::new(undo_delete)
                 on_close=Callback::new(move |_| set_show_toast.set(false))
             />
         </div>
     }
 }
 
+fn highlighted(text: String) -> impl IntoView {
+    let mut segments = Vec::new();
+    let mut rest = text.as_str();
+    while let Some(start) = rest.find(SEARCH_HIGHLIGHT_START) {
+        let (before, after) = rest.split_at(start);
+        let after = &after[SEARCH_HIGHLIGHT_START.len()..];
+        let (marked, remainder) = after
+            .split_once(SEARCH_HIGHLIGHT_END)
+            .unwrap_or((after, ""));
+        segments.push(view! { <span>{before.to_string()}</span> }.into_any());
+        segments.push(view! { <mark class="bg-[#4EC6F0] text-[#191C1D] rounded px-0.5">{marked.to_string()}</mark> }.into_any());
+        rest = remainder;
+    }
+    segments.push(view! { <span>{rest.to_string()}</span> }.into_any());
+    segments
+}
+
 #[component]
 fn SettingsPage() -> impl IntoView {
     let (dark_mode, set_dark_mode) = signal(true);
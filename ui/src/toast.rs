@@ -29,16 +29,19 @@ impl ToastType {
 #[component]
 pub fn Toast(
     visible: ReadSignal<bool>,
-    toast_type: ToastType,
-    title: String,
+    #[prop(into)] toast_type: Signal<ToastType>,
+    #[prop(into)] title: Signal<String>,
     #[prop(optional)] message: Option<String>,
     #[prop(optional)] on_click: Option<Callback<()>>,
+    #[prop(optional, into)] action_label: MaybeProp<String>,
+    #[prop(optional)] on_action: Option<Callback<()>>,
     on_close: Callback<()>,
     #[prop(optional)] auto_dismiss_ms: Option<u32>,
     #[prop(optional)] icon: Option<Children>,
 ) -> impl IntoView {
-    let background_color = toast_type.background_color();
-    let text_color = toast_type.text_color();
+    let background_color =
+        move || format!("background-color: {}", toast_type.get().background_color());
+    let text_color = move || toast_type.get().text_color();
 
     view! {
         <div class=move || format!(
@@ -51,7 +54,7 @@ pub fn Toast(
         )>
             <button
                 class="flex-1 flex flex-row px-10 items-center"
-                style=format!("background-color: {}", background_color)
+                style=background_color
                 on:click=move |_| {
                     if let Some(callback) = on_click {
                         callback.run(());
@@ -66,23 +69,36 @@ pub fn Toast(
                 <div class="flex flex-col flex-1 items-start pl-4">
                     <h1
                         class="text-5xl leading-none pb-2"
-                        style=format!("color: {}", text_color)
+                        style=move || format!("color: {}", text_color())
                     >
                         {title}
                     </h1>
                     {message.map(|msg| view! {
-                        <p class="text-2xl" style=format!("color: {}", text_color)>
+                        <p class="text-2xl" style=move || format!("color: {}", text_color())>
                             {msg}
                         </p>
                     })}
                 </div>
             </button>
+            {move || action_label.get().map(|label| view! {
+                <button
+                    class="px-10 text-4xl font-semibold underline"
+                    style=move || format!("{}; color: {}", background_color(), text_color())
+                    on:click=move |_| {
+                        if let Some(callback) = on_action {
+                            callback.run(());
+                        }
+                    }
+                >
+                    {label}
+                </button>
+            })}
             <button
                 class="p-10"
-                style=format!("background-color: {}", background_color)
+                style=background_color
                 on:click=move |_| on_close.run(())
             >
-                <CloseIcon size=89 color=text_color />
+                {move || view! { <CloseIcon size=89 color=text_color() /> }}
             </button>
             {auto_dismiss_ms.map(|_| view! {
                 <div class=move || format!(